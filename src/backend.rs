@@ -0,0 +1,65 @@
+//!
+//! The backend which controls outputs of compositor. The policy of utility
+//! talks only to [Backend], so it works with niri [Socket](crate::Socket), with
+//! in-memory [FakeBackend](crate::FakeBackend) and with any other compositor
+//! which implements this trait.
+//!
+
+use crate::Result;
+use niri_ipc::{Event, Output, OutputAction, Window, Workspace};
+use std::collections::HashMap;
+
+/// The result of action which compositor may reject with error message
+pub type ActionResult = std::result::Result<(), String>;
+
+/// The stream of compositor events. Ends when compositor closes connection.
+pub type Events = Box<dyn Iterator<Item = Result<Event>> + Send>;
+
+/// The compositor which controls outputs
+pub trait Backend {
+    /// Returns version of compositor
+    fn version(&mut self) -> Result<String>;
+
+    /// Returns connected outputs by their names
+    fn outputs(&mut self) -> Result<HashMap<String, Output>>;
+
+    /// Returns output which has focus, if any
+    fn focused_output(&mut self) -> Result<Option<Output>>;
+
+    /// Apply action to output.
+    ///
+    /// The outer error means compositor is unavailable, the inner one means it
+    /// rejected action.
+    fn output_action(
+        &mut self,
+        output: &str,
+        action: OutputAction,
+    ) -> Result<ActionResult>;
+
+    /// Returns workspaces of all outputs
+    fn workspaces(&mut self) -> Result<Vec<Workspace>>;
+
+    /// Focus workspace, it becomes active on its output
+    fn focus_workspace(&mut self, workspace: u64) -> Result<ActionResult>;
+
+    /// Move workspace to enabled output
+    fn move_workspace(
+        &mut self,
+        workspace: u64,
+        output: &str,
+    ) -> Result<ActionResult>;
+
+    /// Returns windows of all workspaces
+    fn windows(&mut self) -> Result<Vec<Window>>;
+
+    /// Move window to workspace
+    fn move_window(
+        &mut self,
+        window: u64,
+        workspace: u64,
+    ) -> Result<ActionResult>;
+
+    /// Subscribe to compositor events. The events are only used to notice
+    /// changes earlier than periodic poll.
+    fn events(&mut self) -> Result<Events>;
+}
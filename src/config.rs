@@ -0,0 +1,275 @@
+//!
+//! The configuration file of utility. It is TOML file located at
+//! `$XDG_CONFIG_HOME/niri/single-output.toml` by default. Every section is
+//! optional:
+//!
+//! ```toml
+//! # Outputs to choose if none of previously used is connected
+//! prefer = ["desk"]
+//! # Outputs which are not managed by utility at all
+//! exclude = ["eDP-1"]
+//!
+//! # The order to cycle outputs in
+//! [order]
+//! by = "list"
+//! list = ["desk", "tv"]
+//!
+//! # Short names of outputs
+//! [aliases]
+//! desk = "DP-1"
+//! tv = "LG Electronics LG TV SSCR2 0x01010101"
+//!
+//! # Settings to apply when output becomes the active one
+//! [outputs.tv]
+//! mode = "3840x2160@60"
+//! scale = 2.0
+//! transform = "normal"
+//! vrr = true
+//!
+//! # Moving workspaces of outputs being switched off
+//! [workspaces]
+//! migrate = true
+//! order = "output"
+//!
+//! # Outputs to switch on together, the first one is the main one
+//! [profiles.desk]
+//! outputs = ["DP-1", "DP-2"]
+//!
+//! # Settings of output within profile over the ones from `[outputs]`
+//! [profiles.desk.settings.DP-2]
+//! position = { x = 2560, y = 0 }
+//!
+//! # Outputs to switch on when exactly these outputs are connected, the
+//! # excluded ones are counted too
+//! [[rules]]
+//! connected = ["eDP-1", "DP-1", "DP-2"]
+//! profile = "desk"
+//! ```
+//!
+//! The outputs within config are referred by names, identities or aliases.
+//!
+
+use crate::{
+    output_identity, Error, OutputOrder, Profile, Result, Rule,
+    WorkspacesConfig,
+};
+use log::debug;
+use niri_ipc::{
+    ConfiguredPosition, ModeToSet, Output, OutputAction, PositionToSet,
+    ScaleToSet, Transform, VrrToSet,
+};
+use serde::{de, Deserialize, Deserializer};
+use std::{collections::HashMap, env, fmt::Display, fs, io, path::PathBuf};
+
+/// The content of configuration file
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Outputs to choose if none of previously used is connected
+    pub prefer: Vec<String>,
+
+    /// Outputs which are never switched on or off
+    pub exclude: Vec<String>,
+
+    /// The order to cycle outputs in
+    pub order: OrderConfig,
+
+    /// Short names of outputs
+    pub aliases: HashMap<String, String>,
+
+    /// Settings of outputs to apply when output becomes the active one
+    pub outputs: HashMap<String, OutputSettings>,
+
+    /// Moving workspaces of outputs being switched off
+    pub workspaces: WorkspacesConfig,
+
+    /// Named groups of outputs to switch on together
+    pub profiles: HashMap<String, Profile>,
+
+    /// Rules to choose outputs by connected ones, the first matching one wins
+    pub rules: Vec<Rule>,
+}
+
+/// The `[order]` section of config
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OrderConfig {
+    /// The order to cycle outputs in if `--order` is not given
+    pub by: Option<OutputOrder>,
+
+    /// The list of outputs for [OutputOrder::List] if `--list` is not given
+    pub list: Vec<String>,
+}
+
+/// The settings of single output
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputSettings {
+    /// The mode to set in form `<width>x<height>[@<refresh>]` or `auto`
+    #[serde(deserialize_with = "from_str")]
+    pub mode: Option<ModeToSet>,
+
+    /// The scale to set
+    pub scale: Option<f64>,
+
+    /// The transform to set (`normal`, `90`, `flipped-90`, etc.)
+    #[serde(deserialize_with = "from_str")]
+    pub transform: Option<Transform>,
+
+    /// Whether to enable variable refresh rate
+    pub vrr: Option<bool>,
+
+    /// The logical position to set
+    pub position: Option<ConfiguredPosition>,
+}
+
+impl Config {
+    /// Returns default path to configuration file
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = if let Ok(value) = env::var("XDG_CONFIG_HOME") {
+            value
+        } else {
+            env::var("HOME").ok()? + "/.config"
+        };
+
+        Some((config_dir + "/niri/single-output.toml").into())
+    }
+
+    /// Read config from `path` or from default path. The missing file at
+    /// default path is the same as empty config.
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path, true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if !required && err.kind() == io::ErrorKind::NotFound => {
+                debug!("Config file {} does not exist", path.display());
+                return Ok(Self::default());
+            }
+            Err(err) => return Err(Error::Config(path, err.to_string())),
+        };
+        debug!("Read config file {}", path.display());
+        let config: Self = match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(err) => {
+                let line = err.span().map_or(0, |span| {
+                    content[..span.start].matches('\n').count() + 1
+                });
+                let msg = format!("line {line}: {}", err.message());
+                return Err(Error::Config(path, msg));
+            }
+        };
+        for (pos, rule) in config.rules.iter().enumerate() {
+            if let Err(err) = rule.validate(&config) {
+                let msg = format!("rule #{} {err}", pos + 1);
+                return Err(Error::Config(path, msg));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Returns output name or identity if `name` is alias and `name` itself
+    /// otherwise
+    pub fn alias<'a>(&'a self, name: &'a str) -> &'a str {
+        self.aliases.get(name).map_or(name, String::as_str)
+    }
+
+    /// Whether the `output` matches name, identity or alias
+    pub fn matches(&self, output: &Output, name: &str) -> bool {
+        let name = self.alias(name);
+        name == output.name || name == output_identity(output)
+    }
+
+    /// Whether the `output` should not be managed
+    pub fn is_excluded(&self, output: &Output) -> bool {
+        self.exclude.iter().any(|name| self.matches(output, name))
+    }
+
+    /// Returns settings to apply when `output` becomes active
+    pub fn settings(&self, output: &Output) -> Option<&OutputSettings> {
+        self.lookup(&self.outputs, output)
+    }
+
+    /// Returns the entry of `entries` for `output`. Prefers the entry by
+    /// output name, then by identity and then by alias, the aliases are
+    /// compared by name. So the choice does not depend on order of [HashMap].
+    pub(crate) fn lookup<'a, T>(
+        &self,
+        entries: &'a HashMap<String, T>,
+        output: &Output,
+    ) -> Option<&'a T> {
+        entries
+            .get(&output.name)
+            .or_else(|| entries.get(&output_identity(output)))
+            .or_else(|| {
+                entries
+                    .iter()
+                    .filter(|(name, _)| self.matches(output, name))
+                    .min_by_key(|(name, _)| *name)
+                    .map(|(_, entry)| entry)
+            })
+    }
+}
+
+impl OutputSettings {
+    /// Returns niri actions which apply settings
+    pub fn actions(&self) -> Vec<OutputAction> {
+        let mut actions = Vec::new();
+        if let Some(mode) = self.mode {
+            actions.push(OutputAction::Mode { mode });
+        }
+        if let Some(scale) = self.scale {
+            actions.push(OutputAction::Scale {
+                scale: ScaleToSet::Specific(scale),
+            });
+        }
+        if let Some(transform) = self.transform {
+            actions.push(OutputAction::Transform { transform });
+        }
+        if let Some(vrr) = self.vrr {
+            actions.push(OutputAction::Vrr {
+                vrr: VrrToSet {
+                    vrr,
+                    on_demand: false,
+                },
+            });
+        }
+        if let Some(position) = self.position {
+            actions.push(OutputAction::Position {
+                position: PositionToSet::Specific(position),
+            });
+        }
+        actions
+    }
+
+    /// Returns settings with values of `self` and missing ones taken from
+    /// `other`
+    pub fn or(&self, other: &Self) -> Self {
+        Self {
+            mode: self.mode.or(other.mode),
+            scale: self.scale.or(other.scale),
+            transform: self.transform.or(other.transform),
+            vrr: self.vrr.or(other.vrr),
+            position: self.position.or(other.position),
+        }
+    }
+}
+
+/// Deserialize optional value from string with [std::str::FromStr]
+fn from_str<'de, D, T>(
+    deserializer: D,
+) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: Display,
+{
+    let value = String::deserialize(deserializer)?;
+    value.parse().map(Some).map_err(de::Error::custom)
+}
@@ -0,0 +1,157 @@
+//!
+//! Confirmation of risky switches. When switch is run with `--confirm
+//! <SECONDS>`, it remembers pending switch within state file and waits. If
+//! the [confirm](ConfirmSwitch) command is not called within given time, the
+//! previous output is switched back on.
+//!
+
+use crate::{
+    get_outputs, save_state, set_layout, set_output, Config, Context, Error,
+    Layout, Parser, PendingSwitch, Result, Runner, State,
+};
+use log::{info, warn};
+use niri_ipc::Output;
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// The interval to check whether switch is confirmed
+const CONFIRM_POLL: Duration = Duration::from_millis(100);
+
+/// Arguments to require confirmation of switch
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct ConfirmArgs {
+    /// Seconds to wait for confirmation before switching back to previous
+    /// output
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "Switch back unless `confirm` is called within SECONDS"
+    )]
+    pub confirm: Option<u64>,
+}
+
+impl ConfirmArgs {
+    /// Switch on `output` and switch off all others. If confirmation is
+    /// requested, wait for it and switch back to previous outputs or profile
+    /// on timeout.
+    pub(crate) fn switch(
+        &self,
+        ctx: &mut Context,
+        output: &str,
+        outputs: &HashMap<String, Output>,
+    ) -> Result<()> {
+        // There is nothing to revert after dry run
+        let Some(seconds) = self.confirm.filter(|_| !ctx.dry_run) else {
+            return set_output(ctx, output, outputs);
+        };
+
+        let state = State::load(&ctx.statefile)?;
+        let mut enabled: Vec<String> = outputs
+            .values()
+            .filter(|state| state.current_mode.is_some())
+            .map(|state| state.name.clone())
+            .collect();
+        enabled.sort();
+        // The profile is still applied if exactly its outputs are enabled
+        let profile = state.profile.clone().filter(|profile| {
+            Layout::profile(profile, outputs, &ctx.config).is_ok_and(|layout| {
+                layout.outputs.len() == enabled.len()
+                    && enabled.iter().all(|name| layout.contains(name))
+            })
+        });
+        let (previous, others) = match enabled.split_first() {
+            Some((first, others)) => (Some(first.clone()), others.to_vec()),
+            None => (state.active, Vec::new()),
+        };
+        set_output(ctx, output, outputs)?;
+
+        // Nothing to switch back to
+        let Some(previous) = previous.filter(|previous| {
+            previous != output || !others.is_empty() || profile.is_some()
+        }) else {
+            return Ok(());
+        };
+
+        let id = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        let mut state = State::load(&ctx.statefile)?;
+        let pending = PendingSwitch {
+            id,
+            previous,
+            others,
+            profile,
+            output: output.into(),
+        };
+        state.pending = Some(pending.clone());
+        state.save(&ctx.statefile)?;
+
+        info!(
+            "Waiting {seconds} seconds for confirmation of switch to {output}"
+        );
+        let deadline = Instant::now() + Duration::from_secs(seconds);
+        while Instant::now() < deadline {
+            thread::sleep(CONFIRM_POLL);
+            // Either confirmed or replaced by another switch
+            if !is_pending(ctx, id)? {
+                return Ok(());
+            }
+        }
+        if !is_pending(ctx, id)? {
+            return Ok(());
+        }
+
+        let mut state = State::load(&ctx.statefile)?;
+        state.pending = None;
+        state.save(&ctx.statefile)?;
+
+        warn!("Switch to {output} was not confirmed, switching back");
+        let outputs = get_outputs(ctx)?;
+        let layout = previous_layout(&pending, &outputs, &ctx.config)?;
+        set_layout(ctx, &layout, &outputs)?;
+        Err(Error::NotConfirmed(output.into()))
+    }
+}
+
+/// The outputs which were enabled before `pending` switch: its profile, or
+/// its previous output with others which are still connected
+fn previous_layout<'a>(
+    pending: &PendingSwitch,
+    outputs: &'a HashMap<String, Output>,
+    config: &Config,
+) -> Result<Layout<'a>> {
+    if let Some(profile) = &pending.profile {
+        return Layout::profile(profile, outputs, config);
+    }
+    let Some(previous) = outputs.get(&pending.previous) else {
+        return Err(Error::UnknownOutput(pending.previous.clone()));
+    };
+    let others = pending.others.iter().filter_map(|name| outputs.get(name));
+    Ok(Layout::group(
+        [previous].into_iter().chain(others).collect(),
+        config,
+    ))
+}
+
+/// Whether the switch with `id` still waits for confirmation
+fn is_pending(ctx: &Context, id: u64) -> Result<bool> {
+    let state = State::load(&ctx.statefile)?;
+    Ok(state.pending.is_some_and(|pending| pending.id == id))
+}
+
+/// Confirm last switch.
+#[derive(Parser, Debug, Clone)]
+pub struct ConfirmSwitch {}
+
+impl Runner for ConfirmSwitch {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        let mut state = State::load(&ctx.statefile)?;
+        if state.pending.take().is_some() {
+            save_state(ctx, state)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+//!
+//! The daemon which keeps single output active when outputs are connected or
+//! disconnected.
+//!
+//! Niri does not report output changes within its event stream, so the
+//! [Daemon] uses workspace changes, which follow hotplug, only to wake up and
+//! re-read outputs earlier than periodic poll.
+//!
+//! Once output is disconnected, niri has moved its workspaces and windows
+//! already. So the ones seen on last poll are remembered for outputs being
+//! left, to restore them once output connects back.
+//!
+
+use crate::{
+    apply_layout, managed_outputs, print_json, save_state, startup_layout,
+    Context, Error, Layout, OrderArgs, Parser, Result, Runner, State,
+};
+use log::{debug, info, warn};
+use niri_ipc::{Event, Output, Window, Workspace};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io, mem,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+/// Keep single output active on hotplug.
+#[derive(Parser, Debug, Clone)]
+pub struct Daemon {
+    /// Interval in seconds between outputs polls
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Interval in seconds between outputs polls"
+    )]
+    interval: u64,
+
+    /// Outputs to choose if none of previously used is connected
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated list of preferred outputs"
+    )]
+    prefer: Vec<String>,
+
+    /// The order to choose fallback output in
+    #[command(flatten)]
+    order: OrderArgs,
+}
+
+impl Runner for Daemon {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        let events = ctx.backend.events()?;
+        let (wakeup, woken) = mpsc::channel();
+        thread::spawn(move || {
+            for event in events {
+                match event {
+                    // Niri moves workspaces once output is connected or
+                    // disconnected, other events are too frequent to poll on
+                    Ok(Event::WorkspacesChanged { .. }) => (),
+                    Err(Error::Socket(_)) => break,
+                    _ => continue,
+                }
+                if wakeup.send(()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let interval = Duration::from_secs(self.interval);
+        let mut connected = None;
+        let mut seen = None;
+        loop {
+            match self.poll(ctx, &mut connected, &mut seen) {
+                Ok(()) => (),
+                // Niri is gone, so there is nothing to manage
+                Err(err @ Error::Socket(_)) => return Err(err),
+                Err(err) => {
+                    warn!("Failed to handle outputs: {err}");
+                    if ctx.json {
+                        ctx.report.error = Some((&err).into());
+                        print_json(&mem::take(&mut ctx.report));
+                    }
+                }
+            }
+
+            match woken.recv_timeout(interval) {
+                // Many events may come at once, handle them with single poll
+                Ok(()) => while woken.try_recv().is_ok() {},
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(Error::Socket(
+                        io::ErrorKind::UnexpectedEof.into(),
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl Daemon {
+    /// Read outputs and switch them if `connected` ones changed since last
+    /// poll or none is enabled. The workspaces and windows are remembered to
+    /// `seen` even if switch fails.
+    fn poll(
+        &self,
+        ctx: &mut Context,
+        connected: &mut Option<BTreeSet<String>>,
+        seen: &mut Option<Snapshot>,
+    ) -> Result<()> {
+        // The excluded outputs are watched too, as rules may refer them
+        let all = ctx.backend.outputs()?;
+        let outputs = managed_outputs(all.clone(), &ctx.config);
+        let current: BTreeSet<String> = all.keys().cloned().collect();
+        let changed = (connected.as_ref() != Some(&current)
+            || outputs.values().all(|output| output.current_mode.is_none()))
+            && !outputs.is_empty();
+        *connected = Some(current);
+
+        let result = if changed {
+            self.switch(ctx, &all, &outputs, seen.as_ref())
+        } else {
+            Ok(())
+        };
+        *seen = Some(Snapshot {
+            outputs,
+            workspaces: ctx.backend.workspaces()?,
+            windows: ctx.backend.windows()?,
+        });
+        result
+    }
+
+    /// Switch on outputs chosen the same way as `init` does, unless they are
+    /// the only active ones already
+    fn switch(
+        &self,
+        ctx: &mut Context,
+        connected: &HashMap<String, Output>,
+        outputs: &HashMap<String, Output>,
+        seen: Option<&Snapshot>,
+    ) -> Result<()> {
+        let state = State::load(&ctx.statefile)?;
+        let layout = startup_layout(
+            connected,
+            outputs,
+            &state,
+            &ctx.config,
+            &self.prefer,
+            &self.order,
+        )?;
+        let enabled: BTreeSet<&String> = outputs
+            .values()
+            .filter(|output| output.current_mode.is_some())
+            .map(|output| &output.name)
+            .collect();
+        let wanted: BTreeSet<&String> = layout
+            .outputs
+            .iter()
+            .map(|(output, _)| &output.name)
+            .collect();
+        if enabled == wanted {
+            debug!("The {layout} is already the only active one");
+            return Ok(());
+        }
+
+        info!("Outputs changed, switching to {layout}");
+        if let Some(seen) = seen {
+            remember_workspaces(ctx, seen, &layout)?;
+        }
+        apply_layout(ctx, &layout, outputs)?;
+        forget_workspaces(ctx, &layout)?;
+        // The daemon never finishes, so report each switch
+        if ctx.json {
+            print_json(&mem::take(&mut ctx.report));
+        }
+        Ok(())
+    }
+}
+
+/// The outputs, workspaces and windows seen on last poll
+struct Snapshot {
+    outputs: HashMap<String, Output>,
+    workspaces: Vec<Workspace>,
+    windows: Vec<Window>,
+}
+
+/// Remember workspaces and windows of enabled outputs other than ones of
+/// `layout` as they were on last poll
+fn remember_workspaces(
+    ctx: &mut Context,
+    seen: &Snapshot,
+    layout: &Layout,
+) -> Result<()> {
+    let mut state = State::load(&ctx.statefile)?;
+    let left = seen.outputs.values().filter(|output| {
+        output.current_mode.is_some() && !layout.contains(&output.name)
+    });
+    for output in left {
+        state.remember_workspaces(output, &seen.workspaces, &seen.windows);
+    }
+    save_state(ctx, state)
+}
+
+/// Forget workspaces and windows of outputs of `layout` as they are restored
+fn forget_workspaces(ctx: &mut Context, layout: &Layout) -> Result<()> {
+    let mut state = State::load(&ctx.statefile)?;
+    let mut forgotten = false;
+    for (output, _) in &layout.outputs {
+        forgotten |= state.forget_workspaces(output);
+    }
+    if !forgotten {
+        return Ok(());
+    }
+    save_state(ctx, state)
+}
@@ -0,0 +1,119 @@
+//!
+//! The error type of utility. Each [Error] variant has its own exit code, see
+//! [Error::exit_code()].
+//!
+
+use std::{fmt, io, path::PathBuf};
+
+/// The result with utility [Error]
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The errors which may happen during operations with niri outputs
+#[derive(Debug)]
+pub enum Error {
+    /// Niri socket is unavailable or communication with it failed
+    Socket(io::Error),
+
+    /// Niri returned error or unexpected response
+    Niri(String),
+
+    /// Requested output is not connected
+    UnknownOutput(String),
+
+    /// Requested output matches several connected outputs
+    AmbiguousOutput(String, Vec<String>),
+
+    /// Failed to read or write state file
+    State(PathBuf, io::Error),
+
+    /// Failed to find location of state file
+    NoStateDir,
+
+    /// There are no outputs connected to niri
+    NoOutputs,
+
+    /// Failed to read or parse config file
+    Config(PathBuf, String),
+
+    /// Output was switched on, but niri did not enable it
+    NotActivated(String),
+
+    /// Switch to output was not confirmed in time and was reverted
+    NotConfirmed(String),
+
+    /// Requested profile is not configured or has no outputs
+    UnknownProfile(String),
+
+    /// There are no profiles with all outputs connected
+    NoProfiles,
+}
+
+impl Error {
+    /// The process exit code which corresponds to error.
+    ///
+    /// The codes `1` and `2` are skipped as they are used by rust runtime and
+    /// [clap] for panics and invalid arguments.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Error::Socket(_) => 3,
+            Error::Niri(_) => 4,
+            Error::UnknownOutput(_) | Error::AmbiguousOutput(_, _) => 5,
+            Error::State(_, _) | Error::NoStateDir => 6,
+            Error::NoOutputs => 7,
+            Error::Config(_, _) => 8,
+            Error::NotActivated(_) => 9,
+            Error::NotConfirmed(_) => 10,
+            Error::UnknownProfile(_) | Error::NoProfiles => 11,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Socket(err) => {
+                write!(f, "niri socket is unavailable: {err}")
+            }
+            Error::Niri(msg) => write!(f, "niri returned error: {msg}"),
+            Error::UnknownOutput(output) => {
+                write!(f, "output {output} is not connected")
+            }
+            Error::AmbiguousOutput(output, found) => write!(
+                f,
+                "output {output} is ambiguous, matches: {}",
+                found.join(", ")
+            ),
+            Error::State(path, err) => {
+                write!(f, "state file {} failed: {err}", path.display())
+            }
+            Error::NoStateDir => {
+                write!(f, "neither XDG_STATE_HOME nor HOME are set")
+            }
+            Error::NoOutputs => write!(f, "no outputs connected"),
+            Error::Config(path, err) => {
+                write!(f, "config file {} failed: {err}", path.display())
+            }
+            Error::NotActivated(output) => {
+                write!(f, "output {output} did not switch on")
+            }
+            Error::NotConfirmed(output) => {
+                write!(f, "switch to output {output} was not confirmed")
+            }
+            Error::UnknownProfile(profile) => {
+                write!(f, "profile {profile} is not configured")
+            }
+            Error::NoProfiles => {
+                write!(f, "no profile has all its outputs connected")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Socket(err) | Error::State(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//!
+//! The in-memory [Backend] which simulates compositor. It is useful to try
+//! utility policy without real outputs.
+//!
+
+use crate::{ActionResult, Backend, Events, Result};
+use niri_ipc::{
+    Event, LogicalOutput, ModeToSet, Output, OutputAction, PositionToSet,
+    ScaleToSet, Transform, Window, Workspace,
+};
+use std::{
+    collections::HashMap,
+    sync::{mpsc, Arc, Mutex},
+};
+
+/// The compositor with outputs kept in memory.
+///
+/// The clones share the same outputs, so one clone may be passed to utility
+/// while another one is used to plug outputs and check performed actions.
+#[derive(Clone, Default)]
+pub struct FakeBackend {
+    inner: Arc<Mutex<FakeState>>,
+}
+
+/// The shared state of [FakeBackend]
+#[derive(Default)]
+struct FakeState {
+    outputs: HashMap<String, Output>,
+    focused: Option<String>,
+    workspaces: Vec<Workspace>,
+    windows: Vec<Window>,
+    actions: Vec<(String, OutputAction)>,
+    subscribers: Vec<mpsc::Sender<Result<Event>>>,
+}
+
+impl FakeBackend {
+    /// Create compositor with given outputs. The first enabled output is
+    /// focused.
+    pub fn new(outputs: impl IntoIterator<Item = Output>) -> Self {
+        let outputs: HashMap<String, Output> = outputs
+            .into_iter()
+            .map(|output| (output.name.clone(), output))
+            .collect();
+        let focused = outputs
+            .values()
+            .filter(|output| output.current_mode.is_some())
+            .map(|output| output.name.clone())
+            .min();
+        Self {
+            inner: Arc::new(Mutex::new(FakeState {
+                outputs,
+                focused,
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Connect output and notify subscribers
+    pub fn plug(&self, output: Output) {
+        let mut state = self.lock();
+        state.outputs.insert(output.name.clone(), output);
+        state.notify();
+    }
+
+    /// Disconnect output and notify subscribers
+    pub fn unplug(&self, output: &str) {
+        let mut state = self.lock();
+        state.outputs.remove(output);
+        if state.focused.as_deref() == Some(output) {
+            state.focused = None;
+        }
+        state.notify();
+    }
+
+    /// Move focus to output
+    pub fn focus(&self, output: &str) {
+        self.lock().focused = Some(output.into());
+    }
+
+    /// Add workspace after other workspaces of its output
+    pub fn add_workspace(&self, workspace: Workspace) {
+        let mut state = self.lock();
+        state.workspaces.push(workspace);
+        state.reindex();
+    }
+
+    /// Open window, the `workspace_id` of window chooses its workspace
+    pub fn add_window(&self, window: Window) {
+        let mut state = self.lock();
+        let workspace = state
+            .workspaces
+            .iter_mut()
+            .find(|workspace| Some(workspace.id) == window.workspace_id);
+        if let Some(workspace) = workspace {
+            workspace.active_window_id = Some(window.id);
+        }
+        state.windows.push(window);
+    }
+
+    /// Returns actions applied to outputs since last call
+    pub fn take_actions(&self) -> Vec<(String, OutputAction)> {
+        std::mem::take(&mut self.lock().actions)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, FakeState> {
+        // The state is always consistent, so poisoned lock is fine to use
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl FakeState {
+    /// Number workspaces of each output in order of list starting from `1`
+    fn reindex(&mut self) {
+        let mut counts: HashMap<Option<String>, u8> = HashMap::new();
+        for workspace in &mut self.workspaces {
+            let count = counts.entry(workspace.output.clone()).or_default();
+            *count += 1;
+            workspace.idx = *count;
+        }
+    }
+
+    /// Move workspaces of disabled output to first enabled one, like niri
+    /// does
+    fn relocate(&mut self, output: &str) {
+        let target = self
+            .outputs
+            .values()
+            .filter(|output| output.current_mode.is_some())
+            .map(|output| output.name.clone())
+            .min();
+        let (mut moved, kept): (Vec<Workspace>, Vec<Workspace>) = self
+            .workspaces
+            .drain(..)
+            .partition(|ws| ws.output.as_deref() == Some(output));
+        for workspace in &mut moved {
+            workspace.output = target.clone();
+            workspace.is_active = false;
+        }
+        self.workspaces = kept;
+        self.workspaces.append(&mut moved);
+        self.reindex();
+    }
+
+    /// Wake up subscribers, the ones which went away are forgotten
+    fn notify(&mut self) {
+        self.subscribers.retain(|subscriber| {
+            subscriber
+                .send(Ok(Event::WorkspacesChanged { workspaces: vec![] }))
+                .is_ok()
+        });
+    }
+}
+
+impl Backend for FakeBackend {
+    fn version(&mut self) -> Result<String> {
+        Ok("fake".into())
+    }
+
+    fn outputs(&mut self) -> Result<HashMap<String, Output>> {
+        Ok(self.lock().outputs.clone())
+    }
+
+    fn focused_output(&mut self) -> Result<Option<Output>> {
+        let state = self.lock();
+        // Niri moves focus away from disabled output
+        let enabled = |output: &&Output| output.current_mode.is_some();
+        Ok(state
+            .focused
+            .as_ref()
+            .and_then(|name| state.outputs.get(name))
+            .filter(enabled)
+            .or_else(|| {
+                state
+                    .outputs
+                    .values()
+                    .filter(enabled)
+                    .min_by_key(|output| &output.name)
+            })
+            .cloned())
+    }
+
+    fn output_action(
+        &mut self,
+        output: &str,
+        action: OutputAction,
+    ) -> Result<ActionResult> {
+        let mut state = self.lock();
+        state.actions.push((output.into(), action.clone()));
+        // Niri places new output to the right of others
+        let right = state
+            .outputs
+            .values()
+            .filter_map(|output| output.logical)
+            .map(|logical| logical.x + logical.width as i32)
+            .max()
+            .unwrap_or(0);
+        let Some(target) = state.outputs.get_mut(output) else {
+            return Ok(Err("output was missing".into()));
+        };
+        let switched_on = target.current_mode.is_none();
+        let result = apply(target, action);
+        if let (true, Some(logical)) = (switched_on, &mut target.logical) {
+            logical.x = right;
+        }
+        if result.is_ok() && target.current_mode.is_none() {
+            if state.focused.as_deref() == Some(output) {
+                state.focused = None;
+            }
+            state.relocate(output);
+        } else if result.is_ok() && state.focused.is_none() {
+            state.focused = Some(output.into());
+        }
+        Ok(result)
+    }
+
+    fn workspaces(&mut self) -> Result<Vec<Workspace>> {
+        Ok(self.lock().workspaces.clone())
+    }
+
+    fn focus_workspace(&mut self, workspace: u64) -> Result<ActionResult> {
+        let mut state = self.lock();
+        let Some(output) = state
+            .workspaces
+            .iter()
+            .find(|ws| ws.id == workspace)
+            .map(|ws| ws.output.clone())
+        else {
+            return Ok(Err("workspace is missing".into()));
+        };
+        for ws in &mut state.workspaces {
+            ws.is_focused = ws.id == workspace;
+            if ws.output == output {
+                ws.is_active = ws.id == workspace;
+            }
+        }
+        state.focused = output;
+        Ok(Ok(()))
+    }
+
+    fn move_workspace(
+        &mut self,
+        workspace: u64,
+        output: &str,
+    ) -> Result<ActionResult> {
+        let mut state = self.lock();
+        let enabled = state
+            .outputs
+            .get(output)
+            .is_some_and(|output| output.current_mode.is_some());
+        if !enabled {
+            return Ok(Err(format!("output {output} is disabled")));
+        }
+        let Some(pos) =
+            state.workspaces.iter().position(|ws| ws.id == workspace)
+        else {
+            return Ok(Err("workspace is missing".into()));
+        };
+        if state.workspaces[pos].output.as_deref() == Some(output) {
+            return Ok(Ok(()));
+        }
+        let mut moved = state.workspaces.remove(pos);
+        moved.output = Some(output.into());
+        moved.is_active = false;
+        state.workspaces.push(moved);
+        state.reindex();
+        Ok(Ok(()))
+    }
+
+    fn windows(&mut self) -> Result<Vec<Window>> {
+        Ok(self.lock().windows.clone())
+    }
+
+    fn move_window(
+        &mut self,
+        window: u64,
+        workspace: u64,
+    ) -> Result<ActionResult> {
+        let mut state = self.lock();
+        if !state.workspaces.iter().any(|ws| ws.id == workspace) {
+            return Ok(Err("workspace is missing".into()));
+        }
+        let Some(window) = state.windows.iter_mut().find(|w| w.id == window)
+        else {
+            return Ok(Err("window is missing".into()));
+        };
+        window.workspace_id = Some(workspace);
+        Ok(Ok(()))
+    }
+
+    fn events(&mut self) -> Result<Events> {
+        let (sender, receiver) = mpsc::channel();
+        self.lock().subscribers.push(sender);
+        Ok(Box::new(receiver.into_iter()))
+    }
+}
+
+/// Apply action to output the way niri does
+fn apply(output: &mut Output, action: OutputAction) -> ActionResult {
+    match action {
+        OutputAction::Off => {
+            output.current_mode = None;
+            output.logical = None;
+            return Ok(());
+        }
+        OutputAction::On => {
+            if output.current_mode.is_none() {
+                let preferred =
+                    output.modes.iter().position(|mode| mode.is_preferred);
+                output.current_mode = preferred.or(Some(0));
+            }
+        }
+        OutputAction::Mode { mode } => {
+            let found = match mode {
+                ModeToSet::Automatic => {
+                    output.modes.iter().position(|mode| mode.is_preferred)
+                }
+                ModeToSet::Specific(configured) => {
+                    output.modes.iter().position(|mode| {
+                        mode.width == configured.width
+                            && mode.height == configured.height
+                            && configured.refresh.is_none_or(|refresh| {
+                                let rate = f64::from(mode.refresh_rate) / 1000.;
+                                (rate - refresh).abs() < 0.5
+                            })
+                    })
+                }
+            };
+            let Some(found) = found else {
+                return Err(format!("mode {mode:?} is not supported"));
+            };
+            if output.current_mode.is_some() {
+                output.current_mode = Some(found);
+            }
+        }
+        OutputAction::Vrr { vrr } => {
+            if vrr.vrr && !output.vrr_supported {
+                return Err("VRR is not supported".into());
+            }
+            output.vrr_enabled = vrr.vrr;
+        }
+        OutputAction::Scale { scale } => {
+            if let Some(logical) = &mut output.logical {
+                logical.scale = match scale {
+                    ScaleToSet::Automatic => 1.,
+                    ScaleToSet::Specific(scale) => scale,
+                };
+            }
+        }
+        OutputAction::Transform { transform } => {
+            if let Some(logical) = &mut output.logical {
+                logical.transform = transform;
+            }
+        }
+        OutputAction::Position { position } => {
+            if let (PositionToSet::Specific(position), Some(logical)) =
+                (position, &mut output.logical)
+            {
+                logical.x = position.x;
+                logical.y = position.y;
+            }
+        }
+    }
+
+    // Enabled output always has logical configuration
+    if let Some(mode) = output.current_mode {
+        let mode = output.modes[mode];
+        output.logical.get_or_insert(LogicalOutput {
+            x: 0,
+            y: 0,
+            width: u32::from(mode.width),
+            height: u32::from(mode.height),
+            scale: 1.,
+            transform: Transform::Normal,
+        });
+    }
+    Ok(())
+}
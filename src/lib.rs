@@ -7,16 +7,61 @@
 //! Each [command's](Command) emum type implements [Parser] and [Runner] traits
 //! to parse arguments from one side and to perform action from another.
 //!
+//! The commands control outputs through [Backend]. It is niri [Socket] by
+//! default, the [FakeBackend] may be passed to [Args::run_with] to run
+//! commands against outputs kept in memory.
+//!
 #![warn(missing_docs)]
 
+mod backend;
+mod config;
+mod confirm;
+mod daemon;
+mod error;
+mod fake;
+mod list;
+mod logging;
+mod order;
+mod profile;
+mod report;
+mod rule;
+mod socket;
+mod state;
+mod status;
+mod window;
+mod workspace;
+
 use clap::Subcommand;
 pub use clap::{Parser, ValueEnum};
-use niri_ipc::{Output, Request, Response};
-use std::{
-    collections::HashMap,
-    env, fs, io,
-    path::{Path, PathBuf},
+use log::{debug, info, warn};
+use niri_ipc::{Output, OutputAction};
+use serde::Serialize;
+use std::{collections::HashMap, path::PathBuf, thread, time::Duration};
+
+pub use backend::{ActionResult, Backend, Events};
+pub use config::{Config, OrderConfig, OutputSettings};
+pub use confirm::{ConfirmArgs, ConfirmSwitch};
+pub use daemon::Daemon;
+pub use error::{Error, Result};
+pub use fake::FakeBackend;
+pub use list::{ListOutputs, Template};
+pub use logging::LogArgs;
+pub use order::{OrderArgs, OutputOrder};
+use profile::Layout;
+pub use profile::{
+    ApplyProfile, NextProfile, Profile, ProfileAction, ProfileCommand,
+};
+pub use report::{
+    ActionReport, ErrorReport, Report, WindowReport, WorkspaceReport,
+};
+pub use rule::Rule;
+pub use socket::{EventStream, Socket};
+pub use state::{
+    OutputRecord, PendingSwitch, State, WindowRecord, WorkspaceRecord,
+    STATE_VERSION,
 };
+pub use status::{OutputStatus, Status, StatusCommand};
+pub use workspace::{WorkspaceOrder, WorkspacesConfig};
 
 /// Top-level arguments structure
 #[derive(Parser, Debug)]
@@ -36,8 +81,28 @@ pub struct Args {
     path: Option<PathBuf>,
 
     /// Optional path to state file
-    #[arg(short, long, help = "Path to niri socket")]
+    #[arg(short, long, help = "Path to state file")]
     state: Option<PathBuf>,
+
+    /// Optional path to config file
+    #[arg(short, long, help = "Path to config file")]
+    config: Option<PathBuf>,
+
+    /// Whether to print result as JSON
+    #[arg(long, global = true, help = "Print result as JSON")]
+    json: bool,
+
+    /// Whether to only print planned actions
+    #[arg(
+        long,
+        global = true,
+        help = "Print planned actions without sending them to niri or writing state file"
+    )]
+    dry_run: bool,
+
+    /// The verbosity and target of log
+    #[command(flatten)]
+    log: LogArgs,
 }
 
 /// The list of supported commands
@@ -46,77 +111,197 @@ pub struct Args {
 pub enum Command {
     /// Check niri availability.
     ///
-    /// Exits with success if niri is available and panics if niri is
+    /// Exits with success if niri is available and fails if niri is
     /// unavailable.
     #[command(about, long_about)]
     Test(TestSocket),
 
     /// Init outputs at startup.
     ///
-    /// This tries to read the special state file, which holds the name of last
-    /// niri output and attempt to switch it on and other outputs - to switch
-    /// off. If file not found - this will switch on first available output and
-    /// switch off all others.
+    /// This chooses outputs to switch on and switches off all others. If
+    /// connected outputs match one of rules from config, the output or profile
+    /// of rule is chosen. Otherwise this tries to read the special state file,
+    /// which holds last applied profile and the name of last active niri
+    /// output. The profile is chosen if all its outputs are connected, then
+    /// the last output. If it is not connected - this walks previously used
+    /// outputs from most recently used one, then outputs from `--prefer` list.
+    /// If none of them connected - this will switch on first enabled output or
+    /// first output in `--order`.
     #[command(about, long_about)]
     Init(InitOutputs),
 
     /// Switch to next output.
     ///
-    /// This reads all outputs of niri and switch on output which goes after
-    /// first active output and switches off all other outputs.
+    /// This reads all outputs of niri, sorts them according to `--order` and
+    /// switch on output which goes after the focused output and switches off
+    /// all other outputs. If focused output is not enabled, the first enabled
+    /// one is used instead.
     #[command(about, long_about)]
     Next(NextOutput),
+
+    /// Switch to previous output.
+    ///
+    /// Same as `next`, but walks outputs in reverse order: switches on output
+    /// which goes before the focused output and switches off all other
+    /// outputs.
+    #[command(about, long_about)]
+    Prev(PrevOutput),
+
+    /// Switch to chosen output.
+    ///
+    /// Switches on output with given name and switches off all other outputs.
+    /// If there is no output with such name, the output is searched by its
+    /// identity (make, model and serial separated by space) or by part of it
+    /// (case insensitive). Fails if output is not connected or several outputs
+    /// match.
+    #[command(about, long_about)]
+    Switch(SwitchOutput),
+
+    /// Keep single output active on hotplug.
+    ///
+    /// Runs until niri exits. Listens to niri events and periodically polls
+    /// outputs to detect connected and disconnected outputs. On each change
+    /// switches on the outputs chosen the same way as `init` does and switches
+    /// off all other outputs. The remembered active output and profile are not
+    /// changed, so they become active again once connected back, while the
+    /// workspaces and windows of outputs being left are saved to state file.
+    /// The failed switch is logged and the daemon keeps polling, it exits only
+    /// if niri socket is closed.
+    #[command(about, long_about)]
+    Daemon(Daemon),
+
+    /// Confirm last switch.
+    ///
+    /// The `next`, `prev` and `switch` commands called with `--confirm
+    /// <SECONDS>` switch back to previous output unless this command is
+    /// called within given time.
+    #[command(about, long_about)]
+    Confirm(ConfirmSwitch),
+
+    /// Print current outputs and single-output state.
+    ///
+    /// Prints connected outputs with their mode, whether they are enabled and
+    /// whether they are remembered within state file.
+    #[command(about, long_about)]
+    Status(StatusCommand),
+
+    /// List connected outputs in cycle order.
+    ///
+    /// Prints one line per output in the same order `next` visits them. The
+    /// line is formatted with `--format` template, where `{index}`, `{name}`,
+    /// `{identity}`, `{make}`, `{model}`, `{serial}`, `{mode}`, `{enabled}` and
+    /// `{active}` are replaced with output's values, and `\t` and `\n` are
+    /// tab and newline.
+    #[command(about, long_about)]
+    List(ListOutputs),
+
+    /// Switch to profile of outputs.
+    ///
+    /// The profile is named group of outputs from config which are switched
+    /// on together with their own settings, while all other outputs are
+    /// switched off. Switching to single output with other commands leaves
+    /// the profile.
+    #[command(about, long_about)]
+    Profile(ProfileCommand),
+}
+
+/// The environment of command created by [Args]
+pub struct Context {
+    /// The compositor, niri [Socket] unless other is given to
+    /// [run_with](Args::run_with)
+    pub backend: Box<dyn Backend>,
+
+    /// The path to state file
+    pub statefile: PathBuf,
+
+    /// The user configuration
+    pub config: Config,
+
+    /// Whether the result is printed as JSON instead of text
+    pub json: bool,
+
+    /// Whether the actions and state file are only reported, but neither sent
+    /// to niri nor written
+    pub dry_run: bool,
+
+    /// The result of command printed with `--json`
+    pub report: Report,
 }
 
 /// The trait for subcommand
 pub trait Runner {
-    /// The [Args] will create socket for niri and pass it here
-    fn run(self, socket: Socket, statefile: PathBuf);
+    /// The [Args] will create context with backend, state file and config and
+    /// pass it here
+    fn run(self, ctx: &mut Context) -> Result<()>;
 }
 
 impl Args {
-    /// Run chosen subcommand
-    pub fn run(self) {
-        let socket = Socket::connect(self.path);
-        let statefile = self.state.unwrap_or(default_state_file());
-        match self.command {
-            Command::Test(cmd) => cmd.run(socket, statefile),
-            Command::Init(cmd) => cmd.run(socket, statefile),
-            Command::Next(cmd) => cmd.run(socket, statefile),
-        }
+    /// Install logger chosen with `-v` and `--syslog`. Should be called once
+    /// before [run](Args::run).
+    pub fn init_logging(&self) {
+        self.log.init();
     }
-}
 
-/// Wrapper on [niri_ipc::socket::Socket] which allows to reuse single object
-/// for many `send` calls.
-pub struct Socket {
-    path: Option<PathBuf>,
-}
+    /// Run chosen subcommand. With `--json` the [Report] is printed once
+    /// command finishes, even if it fails.
+    pub fn run(self) -> Result<()> {
+        let socket = Socket::connect(self.path.clone());
+        self.run_with(Box::new(socket))
+    }
+
+    /// Run chosen subcommand against given backend instead of niri socket
+    pub fn run_with(self, backend: Box<dyn Backend>) -> Result<()> {
+        let json = self.json;
+        let mut ctx = match self.context(backend) {
+            Ok(ctx) => ctx,
+            Err(err) => {
+                if json {
+                    print_json(&Report {
+                        error: Some((&err).into()),
+                        ..Default::default()
+                    });
+                }
+                return Err(err);
+            }
+        };
 
-impl Socket {
-    /// See [niri_ipc::socket::Socket::connect()] and
-    /// [niri_ipc::socket::Socket::connect_on()]
-    pub fn connect(path: Option<PathBuf>) -> Self {
-        Self { path }
+        let result = self.command.run(&mut ctx);
+        if json {
+            ctx.report.error = result.as_ref().err().map(ErrorReport::from);
+            print_json(&ctx.report);
+        }
+        result
     }
 
-    /// See [niri_ipc::socket::Socket::send()]
-    pub fn send(
-        &self,
-        request: Request,
-    ) -> io::Result<(
-        niri_ipc::Reply,
-        impl FnMut() -> io::Result<niri_ipc::Event>,
-    )> {
-        self.get_socket().send(request)
+    /// Create context of command
+    fn context(&self, backend: Box<dyn Backend>) -> Result<Context> {
+        Ok(Context {
+            backend,
+            statefile: match &self.state {
+                Some(statefile) => statefile.clone(),
+                None => State::default_path()?,
+            },
+            config: Config::load(self.config.clone())?,
+            json: self.json,
+            dry_run: self.dry_run,
+            report: Report::default(),
+        })
     }
+}
 
-    /// Returns [niri_ipc::socket::Socket] or panics
-    pub fn get_socket(&self) -> niri_ipc::socket::Socket {
-        if let Some(path) = &self.path {
-            niri_ipc::socket::Socket::connect_to(path).unwrap()
-        } else {
-            niri_ipc::socket::Socket::connect().unwrap()
+impl Runner for Command {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        match self {
+            Command::Test(cmd) => cmd.run(ctx),
+            Command::Init(cmd) => cmd.run(ctx),
+            Command::Next(cmd) => cmd.run(ctx),
+            Command::Prev(cmd) => cmd.run(ctx),
+            Command::Switch(cmd) => cmd.run(ctx),
+            Command::Daemon(cmd) => cmd.run(ctx),
+            Command::Confirm(cmd) => cmd.run(ctx),
+            Command::Status(cmd) => cmd.run(ctx),
+            Command::List(cmd) => cmd.run(ctx),
+            Command::Profile(cmd) => cmd.run(ctx),
         }
     }
 }
@@ -126,117 +311,486 @@ impl Socket {
 pub struct TestSocket {}
 
 impl Runner for TestSocket {
-    fn run(self, socket: Socket, _statefile: PathBuf) {
-        // Will panic if niri socket is unavailable
-        socket.get_socket();
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        ctx.backend.version()?;
+        Ok(())
     }
 }
 
-fn get_outputs(socket: &Socket) -> HashMap<String, Output> {
-    let result = socket.send(Request::Outputs).unwrap().0.unwrap();
-    if let Response::Outputs(outputs) = result {
-        return outputs;
-    } else {
-        panic!("Unexpected response type form niri")
-    }
+/// Returns outputs managed by utility, the excluded within config are skipped
+fn get_outputs(ctx: &mut Context) -> Result<HashMap<String, Output>> {
+    let outputs = ctx.backend.outputs()?;
+    Ok(managed_outputs(outputs, &ctx.config))
 }
 
-fn default_state_file() -> PathBuf {
-    let state_dir = if let Result::Ok(value) = env::var("XDG_STATE_HOME") {
-        value
-    } else {
-        let home_dir = env::var("HOME").unwrap();
-        home_dir + "/.local/state"
-    };
+/// Returns connected `outputs` without the excluded within `config`
+fn managed_outputs(
+    mut outputs: HashMap<String, Output>,
+    config: &Config,
+) -> HashMap<String, Output> {
+    outputs.retain(|_, output| !config.is_excluded(output));
+    outputs
+}
 
-    (state_dir + "/niri/last-output").into()
+/// Print value as JSON to stdout
+fn print_json<T: Serialize>(value: &T) {
+    // The printed types are plain structures with string keys, so
+    // serialization never fails
+    let json =
+        serde_json::to_string_pretty(value).expect("failed to serialize JSON");
+    println!("{json}");
 }
 
-fn get_last_output(statefile: &Path) -> Option<String> {
-    prepare_statedirs(statefile);
-    fs::read_to_string(statefile).ok()
+/// Returns stable identity of output: its make, model and serial separated by
+/// space.
+///
+/// Unlike name of connector, the identity does not change when monitor is
+/// plugged to another port. The format is the same niri uses to match outputs
+/// within its config, missing serial is replaced with `Unknown`.
+pub fn output_identity(output: &Output) -> String {
+    format!(
+        "{} {} {}",
+        output.make,
+        output.model,
+        output.serial.as_deref().unwrap_or("Unknown")
+    )
 }
 
-fn set_last_output(statefile: &Path, output: &str) {
-    prepare_statedirs(statefile);
-    fs::write(statefile, output).unwrap();
+/// Find connected outputs by name, identity or by part of identity.
+///
+/// The `query` may also be alias from config. The exact match by name is
+/// preferred over exact match by identity (case insensitive), which is
+/// preferred over partial match.
+fn find_output<'a>(
+    outputs: &'a HashMap<String, Output>,
+    config: &Config,
+    query: &str,
+) -> Vec<&'a Output> {
+    let query = config.alias(query);
+    if let Some(output) = outputs.get(query) {
+        return vec![output];
+    }
+
+    let query = query.to_lowercase();
+    let mut found: Vec<&Output> = outputs
+        .values()
+        .filter(|output| output_identity(output).to_lowercase() == query)
+        .collect();
+    if found.is_empty() {
+        found = outputs
+            .values()
+            .filter(|output| {
+                format!("{} {}", output.name, output_identity(output))
+                    .to_lowercase()
+                    .contains(&query)
+            })
+            .collect();
+    }
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    found
 }
 
-fn prepare_statedirs(statefile: &Path) {
-    fs::create_dir_all(statefile.parent().unwrap()).unwrap();
+/// Find single connected output with [find_output()]. Fails if output is not
+/// connected or several outputs match.
+fn resolve_output<'a>(
+    outputs: &'a HashMap<String, Output>,
+    config: &Config,
+    query: &str,
+) -> Result<&'a Output> {
+    match find_output(outputs, config, query)[..] {
+        [] => Err(Error::UnknownOutput(query.into())),
+        [output] => Ok(output),
+        ref found => Err(Error::AmbiguousOutput(
+            query.into(),
+            found.iter().map(|output| output.name.clone()).collect(),
+        )),
+    }
 }
 
+/// Switch on single `output`, switch off all others and remember it within
+/// state file
 fn set_output(
-    socket: &Socket,
+    ctx: &mut Context,
     output: &str,
-    statefile: &Path,
     outputs: &HashMap<String, Output>,
-) {
-    for (out, &_) in outputs.iter() {
-        let action = if out == output {
-            niri_ipc::OutputAction::On
-        } else {
-            niri_ipc::OutputAction::Off
-        };
-        println!("For output {} call {:?}", out, action);
-        socket
-            .send(Request::Output {
-                output: out.into(),
-                action,
-            })
-            .unwrap()
-            .0
-            .unwrap();
+) -> Result<()> {
+    let Some(target) = outputs.get(output) else {
+        return Err(Error::UnknownOutput(output.into()));
+    };
+    set_layout(ctx, &Layout::single(target, &ctx.config), outputs)
+}
+
+/// Switch on outputs of `layout`, switch off all others and remember them
+/// within state file
+fn set_layout(
+    ctx: &mut Context,
+    layout: &Layout,
+    outputs: &HashMap<String, Output>,
+) -> Result<()> {
+    // The workspaces and windows are moved during switch, so remember them
+    // before
+    let workspaces = ctx.backend.workspaces()?;
+    let windows = ctx.backend.windows()?;
+    apply_layout(ctx, layout, outputs)?;
+    let mut state = State::load(&ctx.statefile)?;
+    let active: Vec<&Output> =
+        layout.outputs.iter().map(|(output, _)| *output).collect();
+    state.record(outputs, &workspaces, &windows, &active);
+    state.profile = layout.profile.clone();
+    // New switch cancels the one waiting for confirmation
+    state.pending = None;
+    save_state(ctx, state)
+}
+
+/// Write state file, or only report it with `--dry-run`
+fn save_state(ctx: &mut Context, state: State) -> Result<()> {
+    if !ctx.dry_run {
+        return state.save(&ctx.statefile);
+    }
+    if !ctx.json {
+        let content = serde_json::to_string_pretty(&state)
+            .expect("failed to serialize JSON");
+        println!("Would write {}:\n{content}", ctx.statefile.display());
+    }
+    ctx.report.state = Some(state);
+    Ok(())
+}
+
+/// Switch on outputs of `layout` and switch off all others without touching
+/// state file.
+///
+/// The outputs of `layout` are switched on first and others are switched off
+/// only after niri reports they are enabled, so there is always at least one
+/// active output. The workspaces of others are moved to the main output of
+/// `layout` before switching them off, then the windows remembered for
+/// outputs of `layout` are moved back to their workspaces and the workspaces
+/// remembered for them are focused. If any output fails to switch on, the
+/// ones switched on are switched back off and others are left untouched.
+fn apply_layout(
+    ctx: &mut Context,
+    layout: &Layout,
+    outputs: &HashMap<String, Output>,
+) -> Result<()> {
+    for (pos, (target, settings)) in layout.outputs.iter().enumerate() {
+        if let Err(err) = activate_output(ctx, target, settings) {
+            for (output, _) in &layout.outputs[..=pos] {
+                if output.current_mode.is_none() {
+                    // The error of activation is more important than this one
+                    let _ = send_action(ctx, &output.name, OutputAction::Off);
+                }
+            }
+            return Err(err);
+        }
+    }
+
+    workspace::migrate_workspaces(ctx, layout, outputs)?;
+
+    let mut others: Vec<&String> =
+        outputs.keys().filter(|out| !layout.contains(out)).collect();
+    others.sort();
+    for out in others {
+        send_action(ctx, out, OutputAction::Off)?.map_err(Error::Niri)?;
+    }
+    for (target, _) in &layout.outputs {
+        window::return_windows(ctx, target)?;
+    }
+    // The workspace of main output is focused last, so it keeps focus
+    for (target, _) in layout.outputs.iter().rev() {
+        workspace::restore_workspace(ctx, target)?;
+    }
+
+    let previous = outputs
+        .values()
+        .filter(|state| state.current_mode.is_some())
+        .map(|state| state.name.clone())
+        .min();
+    info!("Switched to {layout}");
+    ctx.report.switched(previous, &layout.main().name);
+    ctx.report.profile = layout.profile.clone();
+    Ok(())
+}
+
+/// Send action for output to backend and add it to report.
+///
+/// Returns whether backend accepted action, so caller decides whether
+/// rejected action is an error. With `--dry-run` the action is not sent and
+/// considered accepted.
+fn send_action(
+    ctx: &mut Context,
+    output: &str,
+    action: OutputAction,
+) -> Result<ActionResult> {
+    if ctx.dry_run {
+        if !ctx.json {
+            println!("For output {output} would call {action:?}");
+        }
+        ctx.report.actions.push(ActionReport {
+            output: output.into(),
+            action,
+            error: None,
+        });
+        return Ok(Ok(()));
+    }
+
+    let result = ctx.backend.output_action(output, action.clone())?;
+    let error = result.as_ref().err().cloned();
+    match &error {
+        None => info!("For output {output} call {action:?}"),
+        Some(err) => {
+            warn!("Niri rejected {action:?} for output {output}: {err}")
+        }
+    }
+    ctx.report.actions.push(ActionReport {
+        output: output.into(),
+        action,
+        error,
+    });
+    Ok(result)
+}
+
+/// The number of attempts to check output is enabled after switching it on
+const ACTIVATION_ATTEMPTS: u32 = 20;
+
+/// The delay between attempts to check output is enabled
+const ACTIVATION_DELAY: Duration = Duration::from_millis(50);
+
+/// Switch on output, apply settings to it and wait until niri reports it is
+/// enabled
+fn activate_output(
+    ctx: &mut Context,
+    output: &Output,
+    settings: &OutputSettings,
+) -> Result<()> {
+    send_action(ctx, &output.name, OutputAction::On)?.map_err(Error::Niri)?;
+    apply_settings(ctx, output, settings)?;
+    if ctx.dry_run {
+        return Ok(());
+    }
+
+    for _ in 0..ACTIVATION_ATTEMPTS {
+        match get_outputs(ctx)?.get(&output.name) {
+            None => return Err(Error::UnknownOutput(output.name.clone())),
+            Some(state) if state.current_mode.is_some() => return Ok(()),
+            Some(_) => {
+                debug!("Waiting for output {} to switch on", output.name);
+                thread::sleep(ACTIVATION_DELAY)
+            }
+        }
+    }
+    Err(Error::NotActivated(output.name.clone()))
+}
+
+/// Apply mode, scale, transform, VRR and position to output which becomes
+/// active. The actions rejected by niri are reported and skipped.
+fn apply_settings(
+    ctx: &mut Context,
+    output: &Output,
+    settings: &OutputSettings,
+) -> Result<()> {
+    for action in settings.actions() {
+        // The rejected settings are reported, but do not fail switch
+        let _ = send_action(ctx, &output.name, action)?;
+    }
+    Ok(())
+}
+
+/// Choose output to switch on at startup or when active output disconnects.
+///
+/// Walks the active output and history from `state`, then the `prefer` list
+/// (or the one from `config` if empty). If none of them connected, returns
+/// first enabled output or first output in `order`.
+fn fallback_output<'a>(
+    outputs: &'a HashMap<String, Output>,
+    state: &State,
+    config: &Config,
+    prefer: &[String],
+    order: &OrderArgs,
+) -> Result<&'a Output> {
+    let remembered = state
+        .active_record()
+        .into_iter()
+        .chain(state.history.iter())
+        .find_map(|record| record.resolve(outputs));
+    if let Some(output) = remembered {
+        return Ok(output);
+    }
+
+    let prefer = if prefer.is_empty() {
+        &config.prefer
+    } else {
+        prefer
+    };
+    let preferred = prefer.iter().find_map(|query| {
+        match find_output(outputs, config, query)[..] {
+            [output] => Some(output),
+            _ => None,
+        }
+    });
+    if let Some(output) = preferred {
+        return Ok(output);
+    }
+
+    let sorted = order.sort(outputs, state, config);
+    sorted
+        .iter()
+        .find(|output| output.current_mode.is_some())
+        .or(sorted.first())
+        .copied()
+        .ok_or(Error::NoOutputs)
+}
+
+/// Choose outputs to switch on at startup or when outputs change: the ones of
+/// the first rule which matches connected outputs, the ones of last applied
+/// profile if all of them are connected, or single output chosen with
+/// [fallback_output()] otherwise.
+fn startup_layout<'a>(
+    connected: &HashMap<String, Output>,
+    outputs: &'a HashMap<String, Output>,
+    state: &State,
+    config: &Config,
+    prefer: &[String],
+    order: &OrderArgs,
+) -> Result<Layout<'a>> {
+    if let Some(layout) = rule::select_layout(connected, outputs, config)? {
+        return Ok(layout);
+    }
+
+    let remembered = state
+        .profile
+        .as_ref()
+        .and_then(|profile| Layout::profile(profile, outputs, config).ok());
+    if let Some(layout) = remembered {
+        return Ok(layout);
     }
-    set_last_output(statefile, output);
+
+    let output = fallback_output(outputs, state, config, prefer, order)?;
+    Ok(Layout::single(output, config))
 }
 
 /// Init outputs at startup.
 #[derive(Parser, Debug, Clone)]
-pub struct InitOutputs {}
+pub struct InitOutputs {
+    /// Outputs to choose if none of previously used is connected
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated list of preferred outputs"
+    )]
+    prefer: Vec<String>,
+
+    /// The order to choose output in if none of known is connected
+    #[command(flatten)]
+    order: OrderArgs,
+}
 
 impl Runner for InitOutputs {
-    fn run(self, socket: Socket, statefile: PathBuf) {
-        let last = get_last_output(&statefile);
-        let outputs = get_outputs(&socket);
-
-        let last = last.unwrap_or({
-            let mut iter = outputs.iter();
-            loop {
-                if let Some((out, state)) = iter.next() {
-                    if state.current_mode.is_some() {
-                        break out.into();
-                    };
-                } else {
-                    break outputs.iter().next().unwrap().0.into();
-                }
-            }
-        });
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        let state = State::load(&ctx.statefile)?;
+        // The rules are matched against excluded outputs too
+        let connected = ctx.backend.outputs()?;
+        let outputs = managed_outputs(connected.clone(), &ctx.config);
+        let layout = startup_layout(
+            &connected,
+            &outputs,
+            &state,
+            &ctx.config,
+            &self.prefer,
+            &self.order,
+        )?;
 
-        set_output(&socket, &last, &statefile, &outputs)
+        set_layout(ctx, &layout, &outputs)
+    }
+}
+
+/// Switch on output which goes after (or before if `backward`) current output
+/// in chosen order. The current output is the focused one if several outputs
+/// are enabled, or the first enabled one.
+fn cycle_output(
+    ctx: &mut Context,
+    order: &OrderArgs,
+    confirm: &ConfirmArgs,
+    backward: bool,
+) -> Result<()> {
+    let outputs = get_outputs(ctx)?;
+    let state = State::load(&ctx.statefile)?;
+    let mut sorted = order.sort(&outputs, &state, &ctx.config);
+    if sorted.is_empty() {
+        return Err(Error::NoOutputs);
+    }
+    if backward {
+        sorted.reverse();
     }
+
+    let focused = ctx.backend.focused_output()?.map(|output| output.name);
+    let current = sorted
+        .iter()
+        .position(|output| Some(&output.name) == focused.as_ref())
+        .filter(|&current| sorted[current].current_mode.is_some())
+        .or_else(|| {
+            sorted
+                .iter()
+                .position(|output| output.current_mode.is_some())
+        });
+    let next = match current {
+        Some(current) => sorted[(current + 1) % sorted.len()],
+        None => sorted[0],
+    };
+
+    confirm.switch(ctx, &next.name, &outputs)
 }
 
 /// Switch to next output.
 #[derive(Parser, Debug, Clone)]
-pub struct NextOutput {}
+pub struct NextOutput {
+    /// The order to cycle outputs in
+    #[command(flatten)]
+    order: OrderArgs,
+
+    /// The confirmation of switch
+    #[command(flatten)]
+    confirm: ConfirmArgs,
+}
+
 impl Runner for NextOutput {
-    fn run(self, socket: Socket, statefile: PathBuf) {
-        let outputs = get_outputs(&socket);
-
-        let mut iter = outputs.iter();
-        loop {
-            if let Some((&_, state)) = iter.next() {
-                if state.current_mode.is_some() {
-                    break;
-                };
-            } else {
-                break;
-            }
-        }
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        cycle_output(ctx, &self.order, &self.confirm, false)
+    }
+}
+
+/// Switch to previous output.
+#[derive(Parser, Debug, Clone)]
+pub struct PrevOutput {
+    /// The order to cycle outputs in
+    #[command(flatten)]
+    order: OrderArgs,
+
+    /// The confirmation of switch
+    #[command(flatten)]
+    confirm: ConfirmArgs,
+}
+
+impl Runner for PrevOutput {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        cycle_output(ctx, &self.order, &self.confirm, true)
+    }
+}
+
+/// Switch to chosen output.
+#[derive(Parser, Debug, Clone)]
+pub struct SwitchOutput {
+    /// Name of output, its identity, part of identity or alias
+    output: String,
+
+    /// The confirmation of switch
+    #[command(flatten)]
+    confirm: ConfirmArgs,
+}
 
-        let next = iter.next().unwrap_or(outputs.iter().next().unwrap()).0;
+impl Runner for SwitchOutput {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        let outputs = get_outputs(ctx)?;
 
-        set_output(&socket, &next, &statefile, &outputs)
+        let output = resolve_output(&outputs, &ctx.config, &self.output)?;
+        self.confirm.switch(ctx, &output.name, &outputs)
     }
 }
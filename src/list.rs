@@ -0,0 +1,214 @@
+//!
+//! The listing of outputs for scripts and pickers like fuzzel, rofi or wofi.
+//!
+
+use crate::{Context, OrderArgs, OutputStatus, Parser, Result, Runner, Status};
+use std::str::FromStr;
+
+/// List connected outputs in cycle order.
+#[derive(Parser, Debug, Clone)]
+pub struct ListOutputs {
+    /// The order to list outputs in
+    #[command(flatten)]
+    order: OrderArgs,
+
+    /// The template of line printed for each output
+    #[arg(
+        long,
+        default_value = "{name}",
+        help = "Template of line printed for each output"
+    )]
+    format: Template,
+}
+
+impl Runner for ListOutputs {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        let status = Status::collect(ctx, &self.order)?;
+        if ctx.json {
+            ctx.report.outputs = Some(status.outputs);
+            return Ok(());
+        }
+        for (index, output) in status.outputs.iter().enumerate() {
+            println!("{}", self.format.render(index, output));
+        }
+        Ok(())
+    }
+}
+
+/// The template of line with `{field}` placeholders.
+///
+/// The supported fields are `index`, `name`, `identity`, `make`, `model`,
+/// `serial`, `mode`, `enabled` and `active`. The `{{` and `}}` are literal
+/// braces, and `\t`, `\n` and `\\` are tab, newline and backslash.
+#[derive(Debug, Clone)]
+pub struct Template(Vec<Piece>);
+
+/// The part of [Template]
+#[derive(Debug, Clone)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+/// The placeholder of [Template]
+#[derive(Debug, Clone, Copy)]
+enum Field {
+    Index,
+    Name,
+    Identity,
+    Make,
+    Model,
+    Serial,
+    Mode,
+    Enabled,
+    Active,
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("unclosed {{{name}"));
+                    }
+                    let field = match name.as_str() {
+                        "index" => Field::Index,
+                        "name" => Field::Name,
+                        "identity" => Field::Identity,
+                        "make" => Field::Make,
+                        "model" => Field::Model,
+                        "serial" => Field::Serial,
+                        "mode" => Field::Mode,
+                        "enabled" => Field::Enabled,
+                        "active" => Field::Active,
+                        _ => return Err(format!("unknown field {{{name}}}")),
+                    };
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                    pieces.push(Piece::Field(field));
+                }
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some('\\') => text.push('\\'),
+                    Some(c) => {
+                        text.push('\\');
+                        text.push(c);
+                    }
+                    None => text.push('\\'),
+                },
+                c => text.push(c),
+            }
+        }
+        pieces.push(Piece::Text(text));
+        Ok(Self(pieces))
+    }
+}
+
+impl Template {
+    /// Render template for output at `index` within list
+    pub fn render(&self, index: usize, output: &OutputStatus) -> String {
+        let mut line = String::new();
+        for piece in &self.0 {
+            match piece {
+                Piece::Text(text) => line += text,
+                Piece::Field(field) => match field {
+                    Field::Index => line += &index.to_string(),
+                    Field::Name => line += &output.name,
+                    Field::Identity => line += &output.identity,
+                    Field::Make => line += &output.make,
+                    Field::Model => line += &output.model,
+                    Field::Serial => {
+                        line += output.serial.as_deref().unwrap_or_default()
+                    }
+                    Field::Mode => {
+                        line += output.mode.as_deref().unwrap_or_default()
+                    }
+                    Field::Enabled => line += &output.enabled.to_string(),
+                    Field::Active => line += &output.active.to_string(),
+                },
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> OutputStatus {
+        OutputStatus {
+            name: "DP-1".into(),
+            identity: "Dell U2720 AAA".into(),
+            make: "Dell".into(),
+            model: "U2720".into(),
+            serial: None,
+            mode: Some("1920x1080@60.000".into()),
+            enabled: true,
+            active: false,
+            remembered: None,
+        }
+    }
+
+    fn render(template: &str) -> String {
+        let template: Template = template.parse().unwrap();
+        template.render(2, &status())
+    }
+
+    #[test]
+    fn fields_are_rendered() {
+        assert_eq!(
+            render("{index} {name} {identity} {make} {model}"),
+            "2 DP-1 Dell U2720 AAA Dell U2720"
+        );
+        assert_eq!(
+            render("[{serial}] {mode} {enabled} {active}"),
+            "[] 1920x1080@60.000 true false"
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(render("{{name}}"), "{name}");
+        assert_eq!(render("{{{name}}}"), "{DP-1}");
+        assert_eq!(render("}"), "}");
+    }
+
+    #[test]
+    fn escapes_are_replaced() {
+        assert_eq!(render(r"{name}\t{make}\n"), "DP-1\tDell\n");
+        assert_eq!(render(r"a\\b"), "a\\b");
+        assert_eq!(render(r"\x\"), "\\x\\");
+    }
+
+    #[test]
+    fn unknown_field_is_rejected() {
+        let err = "{size}".parse::<Template>().unwrap_err();
+        assert_eq!(err, "unknown field {size}");
+        let err = "{name".parse::<Template>().unwrap_err();
+        assert_eq!(err, "unclosed {name");
+    }
+}
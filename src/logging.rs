@@ -0,0 +1,97 @@
+//!
+//! The logging of utility. The messages are written to stderr or, with
+//! `--syslog`, to syslog, which is also read by journald. Only warnings are
+//! shown by default, so key bindings do not flood niri's log.
+//!
+
+use clap::ArgAction;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::process;
+use syslog::{BasicLogger, Facility, Formatter3164};
+
+/// The name of utility within log messages
+const PROCESS: &str = "niri-single-output";
+
+/// Arguments which control logging
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct LogArgs {
+    /// Verbosity: `-v` logs switched outputs, `-vv` logs requests to niri and
+    /// state file access
+    #[arg(
+        short,
+        long,
+        global = true,
+        action = ArgAction::Count,
+        help = "Increase verbosity, may be repeated"
+    )]
+    pub verbose: u8,
+
+    /// Whether to send log to syslog instead of stderr
+    #[arg(
+        long,
+        global = true,
+        help = "Send log to syslog (journald) instead of stderr"
+    )]
+    pub syslog: bool,
+}
+
+impl LogArgs {
+    /// The most verbose level to log
+    pub fn level(&self) -> LevelFilter {
+        match self.verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+
+    /// Install global logger. Does nothing if logger is already installed.
+    ///
+    /// If syslog is unavailable, the log is written to stderr.
+    pub fn init(&self) {
+        let logger: Box<dyn Log> = if self.syslog {
+            let formatter = Formatter3164 {
+                facility: Facility::LOG_USER,
+                hostname: None,
+                process: PROCESS.into(),
+                pid: process::id(),
+            };
+            match syslog::unix(formatter) {
+                Ok(logger) => Box::new(BasicLogger::new(logger)),
+                Err(err) => {
+                    eprintln!("{PROCESS}: syslog is unavailable: {err}");
+                    Box::new(StderrLogger)
+                }
+            }
+        } else {
+            Box::new(StderrLogger)
+        };
+
+        if log::set_boxed_logger(logger).is_ok() {
+            log::set_max_level(self.level());
+        }
+    }
+}
+
+/// The logger which writes messages to stderr
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        match record.level() {
+            Level::Error | Level::Warn => eprintln!(
+                "{PROCESS}: {}: {}",
+                record.level().as_str().to_lowercase(),
+                record.args()
+            ),
+            _ => eprintln!("{PROCESS}: {}", record.args()),
+        }
+    }
+
+    fn flush(&self) {}
+}
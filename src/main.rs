@@ -1,7 +1,13 @@
 use niri_single_output::{Args, Parser};
+use std::process::ExitCode;
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
+    args.init_logging();
 
-    args.run();
+    if let Err(err) = args.run() {
+        eprintln!("niri-single-output: {err}");
+        return ExitCode::from(err.exit_code());
+    }
+    ExitCode::SUCCESS
 }
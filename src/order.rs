@@ -0,0 +1,98 @@
+//!
+//! Ordering of niri outputs. Niri reports outputs as [HashMap], so the order
+//! of iteration is random. The [OrderArgs] gives stable order which is used
+//! to cycle over outputs.
+//!
+
+use crate::{Config, State};
+use clap::ValueEnum;
+use niri_ipc::Output;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The way to order outputs when cycling over them
+#[derive(
+    ValueEnum, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputOrder {
+    /// Order by connector name (e.g. `DP-1`, `HDMI-A-1`)
+    #[default]
+    Name,
+
+    /// Order by make, model and serial of monitor
+    Model,
+
+    /// Order by logical position from left to right and from top to bottom.
+    ///
+    /// Disabled outputs have no logical position, so their last known position
+    /// from state file is used. Outputs with unknown position go after others
+    /// ordered by name.
+    Position,
+
+    /// Order by explicit list of outputs given with `--list`.
+    ///
+    /// The outputs in list are either names, identities of outputs (make,
+    /// model and serial separated by space) or aliases from config.
+    ///
+    /// Outputs which are absent in list go after listed ones ordered by name.
+    List,
+}
+
+/// Arguments to choose outputs order
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct OrderArgs {
+    /// The order to cycle outputs in. The `order.by` from config or
+    /// [OutputOrder::Name] is used by default.
+    #[arg(long, value_enum, help = "Order of outputs [default: name]")]
+    pub order: Option<OutputOrder>,
+
+    /// The explicit list of outputs for [OutputOrder::List]. The `order.list`
+    /// from config is used by default.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma-separated list of outputs for `--order list`"
+    )]
+    pub list: Vec<String>,
+}
+
+impl OrderArgs {
+    /// Returns outputs sorted according to chosen order. The `state` is used
+    /// to find position of disabled outputs and the `config` gives defaults
+    /// for arguments.
+    pub fn sort<'a>(
+        &self,
+        outputs: &'a HashMap<String, Output>,
+        state: &State,
+        config: &Config,
+    ) -> Vec<&'a Output> {
+        let list = if self.list.is_empty() {
+            &config.order.list
+        } else {
+            &self.list
+        };
+        let mut sorted: Vec<&Output> = outputs.values().collect();
+        // Sort by name first, so all other orders falls back to name with
+        // stable sort.
+        sorted.sort_by(|a, b| a.name.cmp(&b.name));
+        match self.order.or(config.order.by).unwrap_or_default() {
+            OutputOrder::Name => (),
+            OutputOrder::Model => sorted.sort_by(|a, b| {
+                (&a.make, &a.model, &a.serial)
+                    .cmp(&(&b.make, &b.model, &b.serial))
+            }),
+            OutputOrder::Position => sorted.sort_by_key(|out| {
+                out.logical
+                    .or_else(|| state.find(out)?.logical)
+                    .map_or((1, 0, 0), |logical| (0, logical.x, logical.y))
+            }),
+            OutputOrder::List => sorted.sort_by_key(|out| {
+                list.iter()
+                    .position(|entry| config.matches(out, entry))
+                    .unwrap_or(list.len())
+            }),
+        }
+        sorted
+    }
+}
@@ -0,0 +1,195 @@
+//!
+//! The profiles of outputs. The profile is named group of outputs which are
+//! switched on together with their own positions and modes, while all other
+//! outputs are switched off. The switch to single output is the special case
+//! of [Layout] with one output and no profile.
+//!
+
+use crate::{
+    get_outputs, resolve_output, set_layout, Config, Context, Error,
+    OutputSettings, Parser, Result, Runner, State,
+};
+use clap::Subcommand;
+use niri_ipc::Output;
+use serde::Deserialize;
+use std::{collections::HashMap, fmt};
+
+/// The `[profiles.<NAME>]` section of config
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Outputs to switch on. The first one is the main one: the workspaces of
+    /// outputs being switched off are moved to it
+    pub outputs: Vec<String>,
+
+    /// Settings of outputs within profile over the ones from
+    /// `[outputs.<OUTPUT>]` section
+    pub settings: HashMap<String, OutputSettings>,
+}
+
+/// The outputs to switch on together
+#[derive(Debug, Clone)]
+pub(crate) struct Layout<'a> {
+    /// The profile outputs belong to
+    pub profile: Option<String>,
+
+    /// The outputs with settings to apply, the first one is the main one
+    pub outputs: Vec<(&'a Output, OutputSettings)>,
+}
+
+impl<'a> Layout<'a> {
+    /// The single `output` with settings from `config`
+    pub fn single(output: &'a Output, config: &Config) -> Self {
+        Self::group(vec![output], config)
+    }
+
+    /// The `outputs` out of profile with settings from `config`, the first
+    /// one is the main one
+    pub fn group(outputs: Vec<&'a Output>, config: &Config) -> Self {
+        let outputs = outputs
+            .into_iter()
+            .map(|output| {
+                let settings =
+                    config.settings(output).cloned().unwrap_or_default();
+                (output, settings)
+            })
+            .collect();
+        Self {
+            profile: None,
+            outputs,
+        }
+    }
+
+    /// The outputs of profile `name`. Fails if profile is not configured or
+    /// any of its outputs is not connected.
+    pub fn profile(
+        name: &str,
+        outputs: &'a HashMap<String, Output>,
+        config: &Config,
+    ) -> Result<Self> {
+        let profile = config
+            .profiles
+            .get(name)
+            .filter(|profile| !profile.outputs.is_empty())
+            .ok_or_else(|| Error::UnknownProfile(name.into()))?;
+
+        let outputs = profile
+            .outputs
+            .iter()
+            .map(|query| {
+                let output = resolve_output(outputs, config, query)?;
+                let defaults =
+                    config.settings(output).cloned().unwrap_or_default();
+                let settings = config
+                    .lookup(&profile.settings, output)
+                    .map_or(defaults.clone(), |settings| {
+                        settings.or(&defaults)
+                    });
+                Ok((output, settings))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            profile: Some(name.into()),
+            outputs,
+        })
+    }
+
+    /// The main output of layout
+    pub fn main(&self) -> &'a Output {
+        self.outputs[0].0
+    }
+
+    /// Whether the `output` is one of layout
+    pub fn contains(&self, output: &str) -> bool {
+        self.outputs.iter().any(|(out, _)| out.name == output)
+    }
+}
+
+impl fmt::Display for Layout<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.profile {
+            Some(profile) => write!(f, "profile {profile}"),
+            None => write!(f, "output {}", self.main().name),
+        }
+    }
+}
+
+/// Switch to profile of outputs.
+#[derive(Parser, Debug, Clone)]
+pub struct ProfileCommand {
+    /// The operation with profiles
+    #[command(subcommand)]
+    action: ProfileAction,
+}
+
+/// The operations with profiles
+#[derive(Subcommand, Debug, Clone)]
+pub enum ProfileAction {
+    /// Switch to profile by name.
+    ///
+    /// Switches on outputs of profile with settings from config and switches
+    /// off all other outputs. Fails if profile is not configured or any of
+    /// its outputs is not connected.
+    #[command(about, long_about)]
+    Apply(ApplyProfile),
+
+    /// Switch to next profile.
+    ///
+    /// Walks configured profiles sorted by name and switches to the one which
+    /// goes after last applied profile. The profiles with outputs which are
+    /// not connected are skipped.
+    #[command(about, long_about)]
+    Next(NextProfile),
+}
+
+/// Switch to profile by name.
+#[derive(Parser, Debug, Clone)]
+pub struct ApplyProfile {
+    /// Name of profile within config
+    name: String,
+}
+
+/// Switch to next profile.
+#[derive(Parser, Debug, Clone)]
+pub struct NextProfile {}
+
+impl Runner for ProfileCommand {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        match self.action {
+            ProfileAction::Apply(cmd) => cmd.run(ctx),
+            ProfileAction::Next(cmd) => cmd.run(ctx),
+        }
+    }
+}
+
+impl Runner for ApplyProfile {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        let outputs = get_outputs(ctx)?;
+        let layout = Layout::profile(&self.name, &outputs, &ctx.config)?;
+        set_layout(ctx, &layout, &outputs)
+    }
+}
+
+impl Runner for NextProfile {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        let outputs = get_outputs(ctx)?;
+        let state = State::load(&ctx.statefile)?;
+        let mut names: Vec<&String> = ctx.config.profiles.keys().collect();
+        names.sort();
+        let available: Vec<Layout> = names
+            .into_iter()
+            .filter_map(|name| {
+                Layout::profile(name, &outputs, &ctx.config).ok()
+            })
+            .collect();
+        if available.is_empty() {
+            return Err(Error::NoProfiles);
+        }
+
+        let next = available
+            .iter()
+            .position(|layout| layout.profile == state.profile)
+            .map_or(0, |current| (current + 1) % available.len());
+        set_layout(ctx, &available[next], &outputs)
+    }
+}
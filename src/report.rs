@@ -0,0 +1,138 @@
+//!
+//! The machine-readable result of command. When utility is run with `--json`,
+//! the [Report] is printed to stdout as JSON instead of human-readable text.
+//!
+
+use crate::{Error, OutputStatus, State, Status, WorkspaceRecord};
+use niri_ipc::OutputAction;
+use serde::Serialize;
+
+/// The result of command
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Report {
+    /// The output which was active before switch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+
+    /// The output which is active after switch, the main one of profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+
+    /// The profile which is applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// The actions sent to niri in order of sending
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<ActionReport>,
+
+    /// The workspaces moved to active output in order of moving
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub workspaces: Vec<WorkspaceReport>,
+
+    /// The windows moved back to their workspaces
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<WindowReport>,
+
+    /// The remembered workspace focused on active output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restored: Option<WorkspaceRecord>,
+
+    /// The status printed by `status` command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+
+    /// The outputs printed by `list` command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<OutputStatus>>,
+
+    /// The content of state file which would be written with `--dry-run`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<State>,
+
+    /// The error command failed with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorReport>,
+}
+
+/// The action sent to niri for output
+#[derive(Serialize, Debug, Clone)]
+pub struct ActionReport {
+    /// The name of output
+    pub output: String,
+
+    /// The action sent
+    pub action: OutputAction,
+
+    /// The error niri rejected action with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The workspace moved from output being switched off
+#[derive(Serialize, Debug, Clone)]
+pub struct WorkspaceReport {
+    /// The unique id of workspace
+    pub id: u64,
+
+    /// The name of workspace if it has one
+    pub name: Option<String>,
+
+    /// The output workspace was on
+    pub from: String,
+
+    /// The output workspace was moved to
+    pub to: String,
+
+    /// The error niri failed to move workspace with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The window moved back to its workspace
+#[derive(Serialize, Debug, Clone)]
+pub struct WindowReport {
+    /// The id of window
+    pub id: u64,
+
+    /// The application id of window
+    pub app_id: Option<String>,
+
+    /// The id of workspace window was moved to
+    pub workspace: u64,
+
+    /// The error niri failed to move window with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The error of command
+#[derive(Serialize, Debug, Clone)]
+pub struct ErrorReport {
+    /// The exit code of process, see [Error::exit_code()]
+    pub code: u8,
+
+    /// The human-readable message
+    pub message: String,
+}
+
+impl Report {
+    /// Remember switch from `previous` output to `active` one. The previous
+    /// output of first switch is kept, so the report of reverted switch shows
+    /// where it started from.
+    pub fn switched(&mut self, previous: Option<String>, active: &str) {
+        if self.active.is_none() {
+            self.previous = previous;
+        }
+        self.active = Some(active.into());
+    }
+}
+
+impl From<&Error> for ErrorReport {
+    fn from(err: &Error) -> Self {
+        Self {
+            code: err.exit_code(),
+            message: err.to_string(),
+        }
+    }
+}
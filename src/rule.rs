@@ -0,0 +1,111 @@
+//!
+//! The automatic choice of outputs. The rules of config are compared with the
+//! set of connected outputs and the first matching one chooses the output or
+//! profile to switch on at startup and on hotplug.
+//!
+
+use crate::{resolve_output, Config, Layout, Result};
+use log::debug;
+use niri_ipc::Output;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The `[[rules]]` section of config
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rule {
+    /// Outputs which are connected. The rule matches only if all of them and
+    /// no others are connected
+    pub connected: Vec<String>,
+
+    /// The output to switch on
+    pub output: Option<String>,
+
+    /// The profile to switch on
+    pub profile: Option<String>,
+}
+
+impl Rule {
+    /// Whether the connected `outputs` are exactly the ones of rule
+    pub fn matches(
+        &self,
+        outputs: &HashMap<String, Output>,
+        config: &Config,
+    ) -> bool {
+        (self.output.is_some() || self.profile.is_some())
+            && outputs.values().all(|output| {
+                self.connected
+                    .iter()
+                    .any(|name| config.matches(output, name))
+            })
+            && self.connected.iter().all(|name| {
+                outputs.values().any(|output| config.matches(output, name))
+            })
+    }
+
+    /// Check the rule refers to either output or known profile, which outputs
+    /// are listed within `connected` (by the same name, identity or alias),
+    /// so the rule may succeed once it matches
+    pub(crate) fn validate(
+        &self,
+        config: &Config,
+    ) -> std::result::Result<(), String> {
+        let targets = match (&self.output, &self.profile) {
+            (Some(output), None) => std::slice::from_ref(output),
+            (None, Some(profile)) => match config.profiles.get(profile) {
+                Some(profile) => &profile.outputs[..],
+                None => {
+                    return Err(format!("refers to unknown profile {profile}"))
+                }
+            },
+            _ => return Err("should have either output or profile".into()),
+        };
+        let connected = |name: &String| {
+            self.connected
+                .iter()
+                .any(|known| config.alias(known) == config.alias(name))
+        };
+        match targets.iter().find(|target| !connected(target)) {
+            Some(target) => {
+                Err(format!("switches on {target} not listed within connected"))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// The outputs to switch on
+    fn layout<'a>(
+        &self,
+        outputs: &'a HashMap<String, Output>,
+        config: &Config,
+    ) -> Result<Layout<'a>> {
+        match (&self.profile, &self.output) {
+            (Some(profile), _) => Layout::profile(profile, outputs, config),
+            (None, output) => {
+                let query = output.as_deref().unwrap_or_default();
+                let output = resolve_output(outputs, config, query)?;
+                Ok(Layout::single(output, config))
+            }
+        }
+    }
+}
+
+/// Choose managed `outputs` with the first rule of `config` which matches
+/// all `connected` outputs, including the excluded ones. Fails if rule
+/// matches, but its outputs can not be switched on.
+pub(crate) fn select_layout<'a>(
+    connected: &HashMap<String, Output>,
+    outputs: &'a HashMap<String, Output>,
+    config: &Config,
+) -> Result<Option<Layout<'a>>> {
+    let Some((pos, rule)) = config
+        .rules
+        .iter()
+        .enumerate()
+        .find(|(_, rule)| rule.matches(connected, config))
+    else {
+        return Ok(None);
+    };
+    debug!("Connected outputs match rule #{}", pos + 1);
+    rule.layout(outputs, config).map(Some)
+}
@@ -0,0 +1,305 @@
+//!
+//! The niri IPC handle. Unlike [niri_ipc::socket::Socket] it is reused for
+//! many requests and reconnects once niri closes connection.
+//!
+
+use crate::{ActionResult, Backend, Error, Events, Result};
+use log::{debug, trace};
+use niri_ipc::{
+    socket::SOCKET_PATH_ENV, Action, Event, LogicalOutput, Output,
+    OutputAction, OutputConfigChanged, Reply, Request, Response, Window,
+    Workspace, WorkspaceReferenceArg,
+};
+use std::{
+    collections::HashMap,
+    env,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+
+/// Connection to niri which is reused for many [send](Socket::send) calls.
+///
+/// The connection is opened on first request. Niri answers single request
+/// per connection and closes it, so the next request finds connection closed
+/// and the socket transparently reconnects and repeats request. Hence every
+/// request but [Request::EventStream] costs its own connection.
+pub struct Socket {
+    path: Option<PathBuf>,
+    stream: Option<BufReader<UnixStream>>,
+}
+
+impl Socket {
+    /// Create socket for niri at `path` or at path from `NIRI_SOCKET`
+    /// environment variable. The connection is opened lazily on first
+    /// request.
+    pub fn connect(path: Option<PathBuf>) -> Self {
+        Self { path, stream: None }
+    }
+
+    /// Send request to niri and return its reply
+    pub fn send(&mut self, request: Request) -> Result<Reply> {
+        let mut buf = serde_json::to_string(&request)
+            .map_err(|err| Error::Socket(err.into()))?;
+        debug!("Sending {buf}");
+        buf.push('\n');
+
+        let mut reconnected = false;
+        loop {
+            match self.exchange(&buf) {
+                Ok(Some(reply)) => {
+                    trace!("Received {}", reply.trim_end());
+                    return serde_json::from_str(&reply)
+                        .map_err(|err| Error::Socket(err.into()));
+                }
+                Ok(None) if !reconnected => (),
+                Ok(None) => {
+                    return Err(Error::Socket(
+                        io::ErrorKind::UnexpectedEof.into(),
+                    ))
+                }
+                Err(err) if !reconnected && is_disconnect(&err) => (),
+                Err(err) => {
+                    self.stream = None;
+                    return Err(Error::Socket(err));
+                }
+            }
+            debug!("Niri closed connection, reconnecting");
+            self.stream = None;
+            reconnected = true;
+        }
+    }
+
+    /// Send request and return successful response or niri error
+    pub fn request(&mut self, request: Request) -> Result<Response> {
+        self.send(request)?.map_err(Error::Niri)
+    }
+
+    /// Perform niri action. The inner error means niri rejected action.
+    pub fn action(&mut self, action: Action) -> Result<ActionResult> {
+        Ok(self.send(Request::Action(action))?.map(|_| ()))
+    }
+
+    /// Open separate connection to niri and subscribe to its events
+    pub fn event_stream(&self) -> Result<EventStream> {
+        let mut socket = Self {
+            path: self.path.clone(),
+            stream: None,
+        };
+        socket.request(Request::EventStream)?;
+        match socket.stream {
+            Some(stream) => Ok(EventStream { stream }),
+            None => Err(Error::Socket(io::ErrorKind::NotConnected.into())),
+        }
+    }
+
+    /// Write request line and read reply line. Returns [None] if niri closed
+    /// connection.
+    fn exchange(&mut self, request: &str) -> io::Result<Option<String>> {
+        let stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => BufReader::new(self.open()?),
+        };
+        let stream = self.stream.insert(stream);
+
+        stream.get_mut().write_all(request.as_bytes())?;
+        stream.get_mut().flush()?;
+
+        let mut reply = String::new();
+        if stream.read_line(&mut reply)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(reply))
+    }
+
+    /// Open new connection to niri
+    fn open(&self) -> io::Result<UnixStream> {
+        match &self.path {
+            Some(path) => UnixStream::connect(path),
+            None => {
+                let path = env::var_os(SOCKET_PATH_ENV).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("{SOCKET_PATH_ENV} is not set"),
+                    )
+                })?;
+                UnixStream::connect(path)
+            }
+        }
+    }
+}
+
+impl Backend for Socket {
+    fn version(&mut self) -> Result<String> {
+        match self.request(Request::Version)? {
+            Response::Version(version) => Ok(version),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn outputs(&mut self) -> Result<HashMap<String, Output>> {
+        match self.request(Request::Outputs)? {
+            Response::Outputs(outputs) => Ok(outputs),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn focused_output(&mut self) -> Result<Option<Output>> {
+        match self.request(Request::FocusedOutput)? {
+            Response::FocusedOutput(output) => Ok(output),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn output_action(
+        &mut self,
+        output: &str,
+        action: OutputAction,
+    ) -> Result<ActionResult> {
+        let reply = self.send(Request::Output {
+            output: output.into(),
+            action,
+        })?;
+        Ok(reply.and_then(|response| match response {
+            Response::OutputConfigChanged(
+                OutputConfigChanged::OutputWasMissing,
+            ) => Err("output was missing".into()),
+            _ => Ok(()),
+        }))
+    }
+
+    fn workspaces(&mut self) -> Result<Vec<Workspace>> {
+        match self.request(Request::Workspaces)? {
+            Response::Workspaces(workspaces) => Ok(workspaces),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn focus_workspace(&mut self, workspace: u64) -> Result<ActionResult> {
+        self.action(Action::FocusWorkspace {
+            reference: WorkspaceReferenceArg::Id(workspace),
+        })
+    }
+
+    /// Niri moves only focused workspace and only to neighbour output, so
+    /// the workspace is moved step by step towards `output` until niri
+    /// reports it is there.
+    fn move_workspace(
+        &mut self,
+        workspace: u64,
+        output: &str,
+    ) -> Result<ActionResult> {
+        for _ in 0..MAX_WORKSPACE_MOVES {
+            let Some(current) = self
+                .workspaces()?
+                .into_iter()
+                .find(|current| current.id == workspace)
+            else {
+                return Ok(Err("workspace is missing".into()));
+            };
+            let Some(source) = current.output else {
+                return Ok(Err("workspace has no output".into()));
+            };
+            if source == output {
+                return Ok(Ok(()));
+            }
+
+            let outputs = self.outputs()?;
+            let logical = |name: &str| outputs.get(name)?.logical;
+            let (Some(from), Some(to)) = (logical(&source), logical(output))
+            else {
+                return Ok(Err(format!("{source} or {output} is disabled")));
+            };
+            if let Err(err) = self.focus_workspace(workspace)? {
+                return Ok(Err(err));
+            }
+            if let Err(err) = self.action(direction(&from, &to))? {
+                return Ok(Err(err));
+            }
+        }
+        Ok(Err(format!("workspace did not reach {output}")))
+    }
+
+    fn windows(&mut self) -> Result<Vec<Window>> {
+        match self.request(Request::Windows)? {
+            Response::Windows(windows) => Ok(windows),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    fn move_window(
+        &mut self,
+        window: u64,
+        workspace: u64,
+    ) -> Result<ActionResult> {
+        self.action(Action::MoveWindowToWorkspace {
+            window_id: Some(window),
+            reference: WorkspaceReferenceArg::Id(workspace),
+        })
+    }
+
+    fn events(&mut self) -> Result<Events> {
+        Ok(Box::new(self.event_stream()?))
+    }
+}
+
+/// The maximum number of steps to move workspace to another output
+const MAX_WORKSPACE_MOVES: usize = 16;
+
+/// Returns action which moves focused workspace from output at `from`
+/// towards output at `to`
+fn direction(from: &LogicalOutput, to: &LogicalOutput) -> Action {
+    let center = |logical: &LogicalOutput| {
+        (
+            i64::from(logical.x) + i64::from(logical.width) / 2,
+            i64::from(logical.y) + i64::from(logical.height) / 2,
+        )
+    };
+    let (from_x, from_y) = center(from);
+    let (to_x, to_y) = center(to);
+    let (dx, dy) = (to_x - from_x, to_y - from_y);
+    match (dx.abs() >= dy.abs(), dx > 0, dy > 0) {
+        (true, true, _) => Action::MoveWorkspaceToMonitorRight {},
+        (true, false, _) => Action::MoveWorkspaceToMonitorLeft {},
+        (false, _, true) => Action::MoveWorkspaceToMonitorDown {},
+        (false, _, false) => Action::MoveWorkspaceToMonitorUp {},
+    }
+}
+
+/// The error about response niri should not send to request
+fn unexpected(response: Response) -> Error {
+    Error::Niri(format!("unexpected response {response:?}"))
+}
+
+/// The stream of niri events. Ends when niri closes connection.
+///
+/// Events unknown to [niri_ipc] are returned as [Error::Niri].
+pub struct EventStream {
+    stream: BufReader<UnixStream>,
+}
+
+impl Iterator for EventStream {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        match self.stream.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => Some(serde_json::from_str(&line).map_err(|err| {
+                Error::Niri(format!("unknown event {}: {err}", line.trim()))
+            })),
+            Err(err) => Some(Err(Error::Socket(err))),
+        }
+    }
+}
+
+/// Whether the error means that niri closed connection
+fn is_disconnect(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+    )
+}
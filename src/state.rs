@@ -0,0 +1,449 @@
+//!
+//! The state file of utility. It remembers active output and history of
+//! previously used outputs in JSON format.
+//!
+//! Older versions of utility stored bare name of last output in state file.
+//! Such files are migrated automatically on [load](State::load).
+//!
+
+use crate::{output_identity, Error, Result};
+use log::{debug, info};
+use niri_ipc::{LogicalOutput, Mode, Output, Window, Workspace};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The current version of state file format
+pub const STATE_VERSION: u32 = 1;
+
+/// The maximum number of outputs remembered in history
+const MAX_HISTORY: usize = 16;
+
+/// The content of state file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct State {
+    /// Version of state file format
+    pub version: u32,
+
+    /// The name of active output
+    pub active: Option<String>,
+
+    /// The name of profile applied last, it is reset by switch to single
+    /// output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// Previously used outputs from most to least recently used
+    #[serde(default)]
+    pub history: Vec<OutputRecord>,
+
+    /// The switch which waits for confirmation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<PendingSwitch>,
+}
+
+/// The switch which waits for confirmation, see
+/// [ConfirmArgs](crate::ConfirmArgs)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PendingSwitch {
+    /// The unique identifier of switch
+    pub id: u64,
+
+    /// The name of output which was active before switch
+    pub previous: String,
+
+    /// The other outputs which were enabled along with `previous`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub others: Vec<String>,
+
+    /// The profile which was applied before switch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+
+    /// The name of output which was switched on
+    pub output: String,
+}
+
+/// The remembered information about output
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OutputRecord {
+    /// The name of output
+    pub name: String,
+
+    /// The stable identity of output, see [output_identity()]
+    #[serde(default)]
+    pub identity: Option<String>,
+
+    /// Unix time in seconds when output was used last time
+    pub last_used: u64,
+
+    /// The mode which was in effect while output was active
+    pub mode: Option<Mode>,
+
+    /// The logical position, scale and transform which were in effect while
+    /// output was active
+    pub logical: Option<LogicalOutput>,
+
+    /// The workspace which was active on output when it was left
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<WorkspaceRecord>,
+
+    /// The windows which were on output when it was left
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<WindowRecord>,
+}
+
+/// The remembered window and its home workspace
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WindowRecord {
+    /// The id of window, it is valid only until niri restarts
+    pub id: u64,
+
+    /// The application id of window
+    pub app_id: Option<String>,
+
+    /// The title of window
+    pub title: Option<String>,
+
+    /// The workspace window was on
+    pub workspace: WorkspaceRecord,
+}
+
+/// The remembered workspace of output
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WorkspaceRecord {
+    /// The id of workspace, it is valid only until niri restarts
+    pub id: u64,
+
+    /// The name of workspace if it has one
+    pub name: Option<String>,
+
+    /// The index of workspace on output
+    pub idx: u8,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            version: STATE_VERSION,
+            active: None,
+            profile: None,
+            history: Vec::new(),
+            pending: None,
+        }
+    }
+}
+
+impl State {
+    /// Returns default path to state file
+    pub fn default_path() -> Result<PathBuf> {
+        let state_dir = if let Ok(value) = env::var("XDG_STATE_HOME") {
+            value
+        } else {
+            let home_dir = env::var("HOME").map_err(|_| Error::NoStateDir)?;
+            home_dir + "/.local/state"
+        };
+
+        Ok((state_dir + "/niri/last-output").into())
+    }
+
+    /// Read state from file. Returns empty state if file does not exist.
+    pub fn load(statefile: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(statefile) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                debug!("State file {} does not exist", statefile.display());
+                return Ok(Self::default());
+            }
+            Err(err) => return Err(Error::State(statefile.into(), err)),
+        };
+        debug!("Read state file {}", statefile.display());
+        Self::parse(&content).map_err(|err| Error::State(statefile.into(), err))
+    }
+
+    /// Parse content of state file migrating it from older formats
+    fn parse(content: &str) -> io::Result<Self> {
+        let content = content.trim();
+        if !content.starts_with('{') {
+            // Plain name of last output
+            info!("Migrating state file from plain output name");
+            let mut state = Self::default();
+            if !content.is_empty() {
+                state.active = Some(content.into());
+                state.history.push(OutputRecord::new(content));
+            }
+            return Ok(state);
+        }
+
+        let state: Self = serde_json::from_str(content)?;
+        if state.version > STATE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported state version {}", state.version),
+            ));
+        }
+        Ok(state)
+    }
+
+    /// Write state to file
+    pub fn save(&self, statefile: &Path) -> Result<()> {
+        let error = |err| Error::State(statefile.into(), err);
+        if let Some(parent) = statefile.parent() {
+            fs::create_dir_all(parent).map_err(error)?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|err| error(err.into()))?;
+        // Write to temporary file first, so the state never left half-written
+        let mut tmpfile = statefile.as_os_str().to_owned();
+        tmpfile.push(".tmp");
+        fs::write(&tmpfile, content + "\n").map_err(error)?;
+        fs::rename(&tmpfile, statefile).map_err(error)?;
+        debug!("Wrote state file {}", statefile.display());
+        Ok(())
+    }
+
+    /// Remember `active` outputs, the first one is remembered as active
+    /// output.
+    ///
+    /// The `outputs`, `workspaces` and `windows` are the ones before switch.
+    /// The mode and logical configuration of enabled outputs are saved to
+    /// history, as well as active workspace and windows of the ones being
+    /// left.
+    pub fn record(
+        &mut self,
+        outputs: &HashMap<String, Output>,
+        workspaces: &[Workspace],
+        windows: &[Window],
+        active: &[&Output],
+    ) {
+        let time = now();
+        for output in outputs.values() {
+            if output.current_mode.is_none() {
+                continue;
+            }
+            let pos = self.entry(output);
+            let record = &mut self.history[pos];
+            record.mode = output.current_mode.map(|mode| output.modes[mode]);
+            record.logical = output.logical;
+            record.last_used = time;
+            if active.iter().any(|active| active.name == output.name) {
+                continue;
+            }
+            self.remember_workspaces(output, workspaces, windows);
+        }
+
+        // The active outputs go first, then most recently used ones
+        let mut first = Vec::new();
+        for output in active {
+            let pos = self.entry(output);
+            let mut record = self.history.remove(pos);
+            record.last_used = time;
+            // The workspace and windows are restored on active outputs
+            // already
+            record.workspace = None;
+            record.windows.clear();
+            first.push(record);
+        }
+        self.history.sort_by_key(|record| Reverse(record.last_used));
+        self.history.splice(0..0, first);
+        self.history.truncate(MAX_HISTORY);
+        self.active = active.first().map(|output| output.name.clone());
+    }
+
+    /// Remember workspace which is active on `output` and `windows` which are
+    /// on its workspaces, replacing the previously remembered ones. The
+    /// unknown output is added to history only if it has any of them.
+    pub fn remember_workspaces(
+        &mut self,
+        output: &Output,
+        workspaces: &[Workspace],
+        windows: &[Window],
+    ) {
+        let workspace = workspaces
+            .iter()
+            .find(|workspace| {
+                workspace.is_active
+                    && workspace.output.as_ref() == Some(&output.name)
+            })
+            .map(WorkspaceRecord::new);
+        let windows: Vec<WindowRecord> = windows
+            .iter()
+            .filter_map(|window| {
+                let workspace = workspaces.iter().find(|workspace| {
+                    Some(workspace.id) == window.workspace_id
+                        && workspace.output.as_ref() == Some(&output.name)
+                })?;
+                Some(WindowRecord {
+                    id: window.id,
+                    app_id: window.app_id.clone(),
+                    title: window.title.clone(),
+                    workspace: WorkspaceRecord::new(workspace),
+                })
+            })
+            .collect();
+        if workspace.is_none()
+            && windows.is_empty()
+            && self.find(output).is_none()
+        {
+            return;
+        }
+        let pos = self.entry(output);
+        let record = &mut self.history[pos];
+        if workspace.is_some() {
+            record.workspace = workspace;
+        }
+        record.windows = windows;
+    }
+
+    /// Forget workspace and windows of `output` once they are restored.
+    /// Returns whether there was anything to forget.
+    pub fn forget_workspaces(&mut self, output: &Output) -> bool {
+        let Some(pos) = self.find(output).and_then(|found| {
+            self.history.iter().position(|record| record == found)
+        }) else {
+            return false;
+        };
+        let record = &mut self.history[pos];
+        let remembered =
+            record.workspace.is_some() || !record.windows.is_empty();
+        record.workspace = None;
+        record.windows.clear();
+        remembered
+    }
+
+    /// Returns record about active output
+    pub fn active_record(&self) -> Option<&OutputRecord> {
+        let active = self.active.as_ref()?;
+        self.history.iter().find(|record| &record.name == active)
+    }
+
+    /// Returns remembered information about connected output
+    pub fn find(&self, output: &Output) -> Option<&OutputRecord> {
+        let identity = output_identity(output);
+        self.history
+            .iter()
+            .find(|record| record.identity.as_ref() == Some(&identity))
+            .or_else(|| {
+                self.history.iter().find(|record| {
+                    record.identity.is_none() && record.name == output.name
+                })
+            })
+    }
+
+    /// Returns index of record about output within history, creating new one
+    /// at the end of history if output is unknown. The name and identity of
+    /// record are updated from `output`.
+    fn entry(&mut self, output: &Output) -> usize {
+        let identity = output_identity(output);
+        let pos = self
+            .history
+            .iter()
+            .position(|record| record.identity.as_ref() == Some(&identity))
+            .or_else(|| {
+                self.history.iter().position(|record| {
+                    record.identity.is_none() && record.name == output.name
+                })
+            });
+        let pos = match pos {
+            Some(pos) => pos,
+            None => {
+                self.history.push(OutputRecord::new(&output.name));
+                self.history.len() - 1
+            }
+        };
+        self.history[pos].name = output.name.clone();
+        self.history[pos].identity = Some(identity);
+        pos
+    }
+}
+
+impl OutputRecord {
+    /// Create record about output without any information
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            identity: None,
+            last_used: 0,
+            mode: None,
+            logical: None,
+            workspace: None,
+            windows: Vec::new(),
+        }
+    }
+
+    /// Find connected output this record is about.
+    ///
+    /// The output is matched by identity first, so it is found even if it is
+    /// connected to another port. The name of output is used as fallback.
+    pub fn resolve<'a>(
+        &self,
+        outputs: &'a HashMap<String, Output>,
+    ) -> Option<&'a Output> {
+        if let Some(identity) = &self.identity {
+            // Several same monitors may be connected, prefer the one on
+            // remembered port
+            let found = outputs
+                .values()
+                .filter(|output| &output_identity(output) == identity)
+                .min_by_key(|output| (output.name != self.name, &output.name));
+            if found.is_some() {
+                return found;
+            }
+        }
+        outputs.get(&self.name)
+    }
+}
+
+impl WorkspaceRecord {
+    /// Create record about workspace
+    pub fn new(workspace: &Workspace) -> Self {
+        Self {
+            id: workspace.id,
+            name: workspace.name.clone(),
+            idx: workspace.idx,
+        }
+    }
+
+    /// Find workspace this record is about.
+    ///
+    /// The workspace is found by id, unless niri reused id for workspace with
+    /// another name. If niri restarted since, it is found by name or by index
+    /// on `output`.
+    pub fn resolve<'a>(
+        &self,
+        workspaces: &'a [Workspace],
+        output: &str,
+    ) -> Option<&'a Workspace> {
+        workspaces
+            .iter()
+            .find(|workspace| {
+                workspace.id == self.id
+                    && (self.name.is_none() || workspace.name == self.name)
+            })
+            .or_else(|| {
+                let name = self.name.as_ref()?;
+                workspaces
+                    .iter()
+                    .find(|workspace| workspace.name.as_ref() == Some(name))
+            })
+            .or_else(|| {
+                workspaces.iter().find(|workspace| {
+                    workspace.output.as_deref() == Some(output)
+                        && workspace.idx == self.idx
+                })
+            })
+    }
+}
+
+/// Current unix time in seconds
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
@@ -0,0 +1,184 @@
+//!
+//! The status of outputs: connected outputs combined with the state file.
+//!
+
+use crate::{
+    get_outputs, output_identity, Context, OrderArgs, Parser, PendingSwitch,
+    Result, Runner, State,
+};
+use niri_ipc::Mode;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Print current outputs and single-output state.
+#[derive(Parser, Debug, Clone)]
+pub struct StatusCommand {}
+
+/// The status of utility
+#[derive(Serialize, Debug, Clone)]
+pub struct Status {
+    /// The path to state file
+    pub statefile: PathBuf,
+
+    /// The name of output remembered as active
+    pub active: Option<String>,
+
+    /// The name of profile applied last
+    pub profile: Option<String>,
+
+    /// The switch which waits for confirmation
+    pub pending: Option<PendingSwitch>,
+
+    /// The connected outputs in cycle order
+    pub outputs: Vec<OutputStatus>,
+}
+
+/// The status of connected output
+#[derive(Serialize, Debug, Clone)]
+pub struct OutputStatus {
+    /// The name of output
+    pub name: String,
+
+    /// The identity of output, see [output_identity()]
+    pub identity: String,
+
+    /// The manufacturer of output
+    pub make: String,
+
+    /// The model of output
+    pub model: String,
+
+    /// The serial of output if known
+    pub serial: Option<String>,
+
+    /// The current mode of output if it is enabled
+    pub mode: Option<String>,
+
+    /// Whether the output is enabled
+    pub enabled: bool,
+
+    /// Whether the output is remembered as active within state file
+    pub active: bool,
+
+    /// The position of output within history of state file, `0` is the most
+    /// recently used one
+    pub remembered: Option<usize>,
+}
+
+impl Status {
+    /// Collect status from niri and state file. The outputs are sorted in
+    /// `order`.
+    pub fn collect(ctx: &mut Context, order: &OrderArgs) -> Result<Self> {
+        let outputs = get_outputs(ctx)?;
+        let state = State::load(&ctx.statefile)?;
+        let active = state
+            .active_record()
+            .and_then(|record| record.resolve(&outputs))
+            .map(|output| output.name.clone());
+
+        let sorted = order.sort(&outputs, &state, &ctx.config);
+        let outputs = sorted
+            .into_iter()
+            .map(|output| OutputStatus {
+                name: output.name.clone(),
+                identity: output_identity(output),
+                make: output.make.clone(),
+                model: output.model.clone(),
+                serial: output.serial.clone(),
+                mode: output
+                    .current_mode
+                    .map(|mode| format_mode(&output.modes[mode])),
+                enabled: output.current_mode.is_some(),
+                active: active.as_ref() == Some(&output.name),
+                remembered: state.find(output).and_then(|record| {
+                    state.history.iter().position(|r| r == record)
+                }),
+            })
+            .collect();
+
+        Ok(Self {
+            statefile: ctx.statefile.clone(),
+            active: state.active,
+            profile: state.profile,
+            pending: state.pending,
+            outputs,
+        })
+    }
+
+    /// Print status as human-readable table
+    pub fn print(&self) {
+        let mut rows = vec![[
+            "NAME".to_string(),
+            "OUTPUT".to_string(),
+            "MODE".to_string(),
+            "ENABLED".to_string(),
+            "REMEMBERED".to_string(),
+        ]];
+        for output in &self.outputs {
+            rows.push([
+                output.name.clone(),
+                format!("{} {}", output.make, output.model),
+                output.mode.clone().unwrap_or("-".into()),
+                if output.enabled { "yes" } else { "no" }.into(),
+                match output.remembered {
+                    _ if output.active => "active".into(),
+                    Some(pos) => format!("#{pos}"),
+                    None => "-".into(),
+                },
+            ]);
+        }
+
+        let mut widths = [0; 5];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        for row in &rows {
+            let line: Vec<String> = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect();
+            println!("{}", line.join("  ").trim_end());
+        }
+
+        println!();
+        println!("State file: {}", self.statefile.display());
+        println!(
+            "Remembered output: {}",
+            self.active.as_deref().unwrap_or("-")
+        );
+        if let Some(profile) = &self.profile {
+            println!("Remembered profile: {profile}");
+        }
+        if let Some(pending) = &self.pending {
+            println!(
+                "Waiting for confirmation of switch from {} to {}",
+                pending.previous, pending.output
+            );
+        }
+    }
+}
+
+impl Runner for StatusCommand {
+    fn run(self, ctx: &mut Context) -> Result<()> {
+        let status = Status::collect(ctx, &OrderArgs::default())?;
+        if ctx.json {
+            ctx.report.status = Some(status);
+        } else {
+            status.print();
+        }
+        Ok(())
+    }
+}
+
+/// Format mode as `<width>x<height>@<refresh>`
+pub(crate) fn format_mode(mode: &Mode) -> String {
+    format!(
+        "{}x{}@{:.3}",
+        mode.width,
+        mode.height,
+        f64::from(mode.refresh_rate) / 1000.
+    )
+}
@@ -0,0 +1,83 @@
+//!
+//! The return of windows to their home output. When output is switched off,
+//! its windows may be moved elsewhere. The windows are remembered within state
+//! file when output is left and moved back to their workspaces once output
+//! becomes active again.
+//!
+
+use crate::{Context, Result, State, WindowRecord, WindowReport};
+use log::{info, warn};
+use niri_ipc::{Output, Window};
+
+/// Move windows remembered for `output` within state file back to their
+/// workspaces.
+///
+/// The window is found by id, unless niri reused id for another application.
+/// If niri restarted since, it is found by application id and title.
+pub(crate) fn return_windows(ctx: &mut Context, output: &Output) -> Result<()> {
+    let state = State::load(&ctx.statefile)?;
+    let Some(record) = state.find(output) else {
+        return Ok(());
+    };
+    if record.windows.is_empty() {
+        return Ok(());
+    }
+
+    let workspaces = ctx.backend.workspaces()?;
+    let mut windows = ctx.backend.windows()?;
+    for remembered in &record.windows {
+        let Some(window) = take_window(&mut windows, remembered) else {
+            continue;
+        };
+        let Some(home) =
+            remembered.workspace.resolve(&workspaces, &output.name)
+        else {
+            continue;
+        };
+        if window.workspace_id == Some(home.id) {
+            continue;
+        }
+
+        let name = window.app_id.as_deref().unwrap_or("unknown");
+        let error = if ctx.dry_run {
+            if !ctx.json {
+                println!("Would move window {name} to workspace {}", home.id);
+            }
+            None
+        } else {
+            ctx.backend.move_window(window.id, home.id)?.err()
+        };
+        match &error {
+            None if ctx.dry_run => (),
+            None => info!("Moved window {name} to workspace {}", home.id),
+            Some(err) => warn!("Failed to move window {name}: {err}"),
+        }
+        ctx.report.windows.push(WindowReport {
+            id: window.id,
+            app_id: window.app_id,
+            workspace: home.id,
+            error,
+        });
+    }
+    Ok(())
+}
+
+/// Find connected window the record is about and remove it from `windows`,
+/// so the same window is not matched twice
+fn take_window(
+    windows: &mut Vec<Window>,
+    remembered: &WindowRecord,
+) -> Option<Window> {
+    let pos = windows
+        .iter()
+        .position(|window| {
+            window.id == remembered.id && window.app_id == remembered.app_id
+        })
+        .or_else(|| {
+            windows.iter().position(|window| {
+                window.app_id == remembered.app_id
+                    && window.title == remembered.title
+            })
+        })?;
+    Some(windows.remove(pos))
+}
@@ -0,0 +1,198 @@
+//!
+//! The migration of workspaces. When outputs are switched off, niri moves
+//! their workspaces to remaining output in its own order. To keep workspaces
+//! in predictable order, they are moved to the output being switched on
+//! before others are switched off.
+//!
+
+use crate::{
+    Context, Layout, OrderArgs, Result, State, WorkspaceRecord, WorkspaceReport,
+};
+use log::{info, warn};
+use niri_ipc::{Output, Workspace};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The `[workspaces]` section of config
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkspacesConfig {
+    /// Whether to move workspaces of outputs being switched off to the output
+    /// being switched on
+    pub migrate: bool,
+
+    /// The order to move workspaces in
+    pub order: WorkspaceOrder,
+}
+
+/// The order to move workspaces to output being switched on
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceOrder {
+    /// All workspaces of first output, then of second one and so on. The
+    /// outputs are walked in cycle order
+    #[default]
+    Output,
+
+    /// The first workspaces of all outputs, then the second ones and so on
+    Index,
+
+    /// Named workspaces sorted by name, then unnamed ones in `output` order
+    Name,
+}
+
+impl Default for WorkspacesConfig {
+    fn default() -> Self {
+        Self {
+            migrate: true,
+            order: WorkspaceOrder::default(),
+        }
+    }
+}
+
+/// Move workspaces from enabled outputs other than ones of `layout` to its
+/// main output.
+///
+/// The empty unnamed workspaces are skipped as niri creates and removes them
+/// on its own. The focused workspace is focused again once moved.
+pub(crate) fn migrate_workspaces(
+    ctx: &mut Context,
+    layout: &Layout,
+    outputs: &HashMap<String, Output>,
+) -> Result<()> {
+    if !ctx.config.workspaces.migrate {
+        return Ok(());
+    }
+    let target = layout.main().name.as_str();
+
+    // The moved outputs are enabled, so their live positions are used and
+    // the state is not needed for ordering
+    let sorted =
+        OrderArgs::default().sort(outputs, &State::default(), &ctx.config);
+    let rank = |name: &str| {
+        sorted
+            .iter()
+            .position(|output| output.name == name)
+            .unwrap_or(usize::MAX)
+    };
+
+    let mut moved: Vec<(Workspace, usize)> = ctx
+        .backend
+        .workspaces()?
+        .into_iter()
+        .filter(|workspace| {
+            workspace.name.is_some() || workspace.active_window_id.is_some()
+        })
+        .filter_map(|workspace| {
+            let source = outputs.get(workspace.output.as_deref()?)?;
+            if layout.contains(&source.name) || source.current_mode.is_none() {
+                return None;
+            }
+            let rank = rank(&source.name);
+            Some((workspace, rank))
+        })
+        .collect();
+    match ctx.config.workspaces.order {
+        WorkspaceOrder::Output => {
+            moved.sort_by_key(|(workspace, rank)| (*rank, workspace.idx))
+        }
+        WorkspaceOrder::Index => {
+            moved.sort_by_key(|(workspace, rank)| (workspace.idx, *rank))
+        }
+        WorkspaceOrder::Name => moved.sort_by(|(a, a_rank), (b, b_rank)| {
+            // The named ones go first
+            (a.name.is_none(), &a.name, a_rank, a.idx).cmp(&(
+                b.name.is_none(),
+                &b.name,
+                b_rank,
+                b.idx,
+            ))
+        }),
+    }
+
+    let mut focused = None;
+    for (workspace, _) in moved {
+        let from = workspace.output.clone().unwrap_or_default();
+        let name = describe(&workspace);
+        let error = if ctx.dry_run {
+            if !ctx.json {
+                println!("Would move workspace {name} from {from} to {target}");
+            }
+            None
+        } else {
+            ctx.backend.move_workspace(workspace.id, target)?.err()
+        };
+        match &error {
+            None if ctx.dry_run => (),
+            None => {
+                info!("Moved workspace {name} from {from} to {target}");
+                if workspace.is_focused {
+                    focused = Some(workspace.id);
+                }
+            }
+            Some(err) => warn!(
+                "Failed to move workspace {name} from {from} to {target}: {err}"
+            ),
+        }
+        ctx.report.workspaces.push(WorkspaceReport {
+            id: workspace.id,
+            name: workspace.name,
+            from,
+            to: target.into(),
+            error,
+        });
+    }
+
+    // Moving workspaces changes focus, so return it back
+    if let Some(focused) = focused {
+        if let Err(err) = ctx.backend.focus_workspace(focused)? {
+            warn!("Failed to focus workspace {focused}: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// Focus workspace remembered for `output` within state file, see
+/// [WorkspaceRecord::resolve()].
+pub(crate) fn restore_workspace(
+    ctx: &mut Context,
+    output: &Output,
+) -> Result<()> {
+    let state = State::load(&ctx.statefile)?;
+    let Some(remembered) = state
+        .find(output)
+        .and_then(|record| record.workspace.clone())
+    else {
+        return Ok(());
+    };
+
+    let workspaces = ctx.backend.workspaces()?;
+    let Some(found) = remembered.resolve(&workspaces, &output.name) else {
+        return Ok(());
+    };
+
+    let name = describe(found);
+    if ctx.dry_run {
+        if !ctx.json {
+            println!("Would focus workspace {name} on {}", output.name);
+        }
+    } else {
+        match ctx.backend.focus_workspace(found.id)? {
+            Ok(()) => info!("Focused workspace {name} on {}", output.name),
+            Err(err) => {
+                warn!("Failed to focus workspace {name}: {err}");
+                return Ok(());
+            }
+        }
+    }
+    ctx.report.restored = Some(WorkspaceRecord::new(found));
+    Ok(())
+}
+
+/// Returns name of workspace or its index if it has no name
+pub(crate) fn describe(workspace: &Workspace) -> String {
+    match &workspace.name {
+        Some(name) => name.clone(),
+        None => format!("#{}", workspace.idx),
+    }
+}
@@ -0,0 +1,155 @@
+mod common;
+
+use common::{output, outputs, InMemory};
+use niri_ipc::{OutputAction, ScaleToSet, Window, Workspace};
+use niri_single_output::{Backend, FakeBackend};
+use std::{
+    fs, thread,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn commands_run_against_fake_backend() {
+    let fake = InMemory::new(outputs());
+    let backend = fake.backend();
+
+    fake.run(&["next"]).unwrap();
+    assert_eq!(fake.enabled(), ["DP-2"]);
+    let actions: Vec<String> = backend
+        .take_actions()
+        .into_iter()
+        .map(|(output, action)| format!("{output} {action:?}"))
+        .collect();
+    assert_eq!(actions, ["DP-2 On", "DP-1 Off", "HDMI-A-1 Off"]);
+
+    backend.unplug("DP-2");
+    fake.run(&["init"]).unwrap();
+    assert_eq!(fake.enabled(), ["DP-1"]);
+}
+
+/// Wait until `check` succeeds, up to few seconds
+fn wait(check: impl FnMut() -> bool) -> bool {
+    wait_for(Duration::from_secs(5), check)
+}
+
+/// Wait until `check` succeeds, up to `timeout`
+fn wait_for(timeout: Duration, mut check: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if check() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+#[test]
+fn daemon_returns_windows_on_reconnect() {
+    let mut outputs = outputs();
+    outputs[1] = output("DP-2", "Dell", "P2419", None, true);
+    let fake = InMemory::new(outputs);
+    let mut backend = fake.backend();
+    for (id, name) in [(1, "mail"), (2, "code")] {
+        backend.add_workspace(Workspace {
+            id,
+            idx: 0,
+            name: Some(name.into()),
+            output: Some("DP-1".into()),
+            is_active: id == 1,
+            is_focused: false,
+            active_window_id: None,
+        });
+    }
+    backend.add_window(Window {
+        id: 7,
+        title: Some("main.rs".into()),
+        app_id: Some("editor".into()),
+        workspace_id: Some(2),
+        is_focused: false,
+    });
+
+    let daemon = fake.clone();
+    thread::spawn(move || daemon.run(&["daemon"]).unwrap());
+    // The daemon takes windows right after it switched others off
+    assert!(wait(|| fake.enabled() == ["DP-1"]));
+    thread::sleep(Duration::from_millis(100));
+
+    backend.unplug("DP-1");
+    assert!(wait(|| fake.enabled() == ["DP-2"]));
+    let state = fs::read_to_string(fake.statefile()).unwrap();
+    assert!(state.contains("editor"));
+
+    // Niri moves windows of disconnected output elsewhere
+    backend.move_window(7, 1).unwrap().unwrap();
+    backend.plug(output("DP-1", "Dell", "U2720", Some("AAA"), false));
+    assert!(wait(|| {
+        backend.windows().unwrap()[0].workspace_id == Some(2)
+    }));
+}
+
+#[test]
+fn daemon_survives_failed_switch() {
+    let mut outputs = outputs();
+    outputs[1] = output("DP-2", "Dell", "P2419", None, true);
+    let fake = InMemory::new(outputs);
+    // The state file can not be read, so the switch fails
+    fs::create_dir(fake.statefile()).unwrap();
+
+    let daemon = fake.clone();
+    thread::spawn(move || daemon.run(&["daemon"]).unwrap());
+    assert!(!wait_for(Duration::from_millis(200), || {
+        fake.enabled() == ["DP-1"]
+    }));
+
+    fs::remove_dir(fake.statefile()).unwrap();
+    fake.backend().unplug("HDMI-A-1");
+    assert!(wait(|| fake.enabled() == ["DP-1"]));
+}
+
+#[test]
+fn daemon_follows_rules_on_hotplug() {
+    let fake = InMemory::new([outputs().remove(0)]);
+    fake.config(
+        r#"
+        [[rules]]
+        connected = ["DP-1"]
+        output = "DP-1"
+
+        [[rules]]
+        connected = ["DP-1", "DP-2"]
+        output = "DP-2"
+        "#,
+    );
+    let backend = fake.backend();
+
+    let daemon = fake.clone();
+    thread::spawn(move || daemon.run(&["daemon"]).unwrap());
+    // The dock is attached, so only external output is used
+    backend.plug(outputs().remove(1));
+    assert!(wait(|| fake.enabled() == ["DP-2"]));
+
+    backend.unplug("DP-2");
+    assert!(wait(|| fake.enabled() == ["DP-1"]));
+}
+
+#[test]
+fn fake_backend_applies_settings() {
+    let mut backend =
+        FakeBackend::new([output("DP-1", "Dell", "U2720", None, true)]);
+    let mode = "1920x1080".parse().unwrap();
+    let scale = ScaleToSet::Specific(2.);
+    backend
+        .output_action("DP-1", OutputAction::Mode { mode })
+        .unwrap()
+        .unwrap();
+    backend
+        .output_action("DP-1", OutputAction::Scale { scale })
+        .unwrap()
+        .unwrap();
+
+    let outputs = backend.outputs().unwrap();
+    assert_eq!(outputs["DP-1"].logical.unwrap().scale, 2.);
+    let missing = backend.output_action("DP-2", OutputAction::On).unwrap();
+    assert!(missing.is_err());
+}
@@ -0,0 +1,528 @@
+//!
+//! The fake niri for integration tests. It listens on Unix socket within
+//! temporary directory and speaks the [niri_ipc] JSON protocol on top of
+//! [FakeBackend], which keeps outputs, workspaces and windows. The
+//! [Request::Output] calls are recorded, so tests check what utility asked
+//! niri to do. The [InMemory] runs utility against [FakeBackend] without
+//! socket.
+//!
+
+#![allow(dead_code)]
+
+use niri_ipc::{
+    Action, LogicalOutput, Mode, Output, OutputAction, OutputConfigChanged,
+    Reply, Request, Response, Transform, Window, Workspace,
+    WorkspaceReferenceArg,
+};
+use niri_single_output::{
+    ActionResult, Args, Backend, FakeBackend, Parser, Result, State,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{Arc, Mutex},
+    thread,
+};
+use tempfile::TempDir;
+
+/// The records and faults of fake niri shared with connection threads
+#[derive(Default)]
+struct Shared {
+    calls: Vec<String>,
+    connections: usize,
+    dead: HashSet<String>,
+    failing: HashSet<String>,
+}
+
+/// The fake niri listening on socket within temporary directory
+pub struct FakeNiri {
+    dir: TempDir,
+    backend: FakeBackend,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl FakeNiri {
+    /// Start fake niri with given outputs
+    pub fn new(outputs: impl IntoIterator<Item = Output>) -> Self {
+        let dir = TempDir::new().expect("failed to create temporary dir");
+        let backend = FakeBackend::new(outputs);
+        let shared = Arc::new(Mutex::new(Shared::default()));
+
+        let listener = UnixListener::bind(dir.path().join("niri.sock"))
+            .expect("failed to bind socket");
+        let (server, faults) = (backend.clone(), shared.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { break };
+                let backend = server.clone();
+                let shared = faults.clone();
+                thread::spawn(move || serve(stream, backend, &shared));
+            }
+        });
+
+        fs::write(dir.path().join("config.toml"), "")
+            .expect("failed to write config");
+        Self {
+            dir,
+            backend,
+            shared,
+        }
+    }
+
+    /// Path to socket of fake niri
+    pub fn socket(&self) -> PathBuf {
+        self.dir.path().join("niri.sock")
+    }
+
+    /// Path to state file used by [run](Self::run)
+    pub fn statefile(&self) -> PathBuf {
+        self.dir.path().join("state")
+    }
+
+    /// Replace config file used by [run](Self::run)
+    pub fn config(&self, content: &str) {
+        fs::write(self.dir.path().join("config.toml"), content)
+            .expect("failed to write config");
+    }
+
+    /// Make output to ignore requests to switch it on
+    pub fn dead(&self, output: &str) {
+        self.shared.lock().unwrap().dead.insert(output.into());
+    }
+
+    /// Make output to reject every action with error
+    pub fn failing(&self, output: &str) {
+        self.shared.lock().unwrap().failing.insert(output.into());
+    }
+
+    /// Move focus to output
+    pub fn focus(&self, output: &str) {
+        self.backend.focus(output);
+    }
+
+    /// Add workspace after other workspaces of output and return its id. The
+    /// `windows` tells whether workspace has windows.
+    pub fn workspace(
+        &self,
+        output: &str,
+        name: Option<&str>,
+        windows: bool,
+    ) -> u64 {
+        let workspaces = self.backend().workspaces().unwrap();
+        let id = workspaces.len() as u64 + 1;
+        // Every output has one active workspace
+        let first = workspaces
+            .iter()
+            .all(|ws| ws.output.as_deref() != Some(output));
+        self.backend.add_workspace(Workspace {
+            id,
+            idx: 0,
+            name: name.map(Into::into),
+            output: Some(output.into()),
+            is_active: first,
+            is_focused: false,
+            active_window_id: windows.then_some(id * 100),
+        });
+        id
+    }
+
+    /// Workspaces of output in their order as names, or ids for unnamed ones
+    pub fn workspaces(&self, output: &str) -> Vec<String> {
+        let mut found: Vec<Workspace> = self
+            .backend()
+            .workspaces()
+            .unwrap()
+            .into_iter()
+            .filter(|ws| ws.output.as_deref() == Some(output))
+            .collect();
+        found.sort_by_key(|ws| ws.idx);
+        found
+            .into_iter()
+            .map(|ws| ws.name.unwrap_or(ws.id.to_string()))
+            .collect()
+    }
+
+    /// Focus workspace
+    pub fn focus_workspace(&self, id: u64) {
+        self.backend().focus_workspace(id).unwrap().unwrap();
+    }
+
+    /// The id of focused workspace
+    pub fn focused_workspace(&self) -> Option<u64> {
+        let workspaces = self.backend().workspaces().unwrap();
+        workspaces.iter().find(|ws| ws.is_focused).map(|ws| ws.id)
+    }
+
+    /// Open window on workspace and return its id
+    pub fn window(&self, workspace: u64, app_id: &str, title: &str) -> u64 {
+        let id = self.backend().windows().unwrap().len() as u64 + 1;
+        self.backend.add_window(Window {
+            id,
+            title: Some(title.into()),
+            app_id: Some(app_id.into()),
+            workspace_id: Some(workspace),
+            is_focused: false,
+        });
+        id
+    }
+
+    /// Move window to workspace
+    pub fn move_window(&self, window: u64, workspace: u64) {
+        self.backend()
+            .move_window(window, workspace)
+            .unwrap()
+            .unwrap();
+    }
+
+    /// The id of workspace window is on
+    pub fn window_workspace(&self, window: u64) -> Option<u64> {
+        let windows = self.backend().windows().unwrap();
+        windows
+            .into_iter()
+            .find(|w| w.id == window)
+            .and_then(|w| w.workspace_id)
+    }
+
+    /// Disconnect output
+    pub fn unplug(&self, output: &str) {
+        self.backend.unplug(output);
+    }
+
+    /// Connect output
+    pub fn plug(&self, output: Output) {
+        self.backend.plug(output);
+    }
+
+    /// Number of connections accepted, one per request
+    pub fn connections(&self) -> usize {
+        self.shared.lock().unwrap().connections
+    }
+
+    /// Recorded output actions as `<output> <action>` and clear them
+    pub fn calls(&self) -> Vec<String> {
+        std::mem::take(&mut self.shared.lock().unwrap().calls)
+    }
+
+    /// Names of enabled outputs sorted
+    pub fn enabled(&self) -> Vec<String> {
+        enabled(self.backend().outputs().unwrap().values())
+    }
+
+    /// Read state file
+    pub fn state(&self) -> State {
+        State::load(&self.statefile()).expect("failed to load state")
+    }
+
+    /// Run utility with arguments against fake niri, temporary state file and
+    /// config
+    pub fn run(&self, args: &[&str]) -> Result<()> {
+        let socket = ["--path".into(), self.socket().into_os_string()];
+        parse(self.dir.path(), socket, args).run()
+    }
+
+    /// Run utility binary with arguments against fake niri, temporary state
+    /// file and config. Returns exit code and printed stdout.
+    pub fn exec(&self, args: &[&str]) -> (i32, String) {
+        let socket = ["--path".into(), self.socket().into_os_string()];
+        let output = Command::new(env!("CARGO_BIN_EXE_niri-single-output"))
+            .args(argv(self.dir.path(), socket, args))
+            .output()
+            .expect("failed to run utility");
+        let stdout = String::from_utf8(output.stdout).expect("invalid stdout");
+        (output.status.code().expect("utility was killed"), stdout)
+    }
+
+    /// Run utility binary with `--json` and parse printed report
+    pub fn report(&self, args: &[&str]) -> Value {
+        let (_, stdout) = self.exec(&[&["--json"], args].concat());
+        serde_json::from_str(&stdout).expect("failed to parse report")
+    }
+
+    fn backend(&self) -> FakeBackend {
+        self.backend.clone()
+    }
+}
+
+/// The [FakeBackend] with temporary state file and config. The clones share
+/// the same backend and directory, so one clone may run daemon in thread.
+#[derive(Clone)]
+pub struct InMemory {
+    dir: Arc<TempDir>,
+    backend: FakeBackend,
+}
+
+impl InMemory {
+    /// Create backend with given outputs
+    pub fn new(outputs: impl IntoIterator<Item = Output>) -> Self {
+        let dir = TempDir::new().expect("failed to create temporary dir");
+        fs::write(dir.path().join("config.toml"), "")
+            .expect("failed to write config");
+        Self {
+            dir: Arc::new(dir),
+            backend: FakeBackend::new(outputs),
+        }
+    }
+
+    /// The backend utility runs against
+    pub fn backend(&self) -> FakeBackend {
+        self.backend.clone()
+    }
+
+    /// Path to state file used by [run](Self::run)
+    pub fn statefile(&self) -> PathBuf {
+        self.dir.path().join("state")
+    }
+
+    /// Replace config file used by [run](Self::run)
+    pub fn config(&self, content: &str) {
+        fs::write(self.dir.path().join("config.toml"), content)
+            .expect("failed to write config");
+    }
+
+    /// Names of enabled outputs sorted
+    pub fn enabled(&self) -> Vec<String> {
+        let outputs = self.backend().outputs().expect("failed to get outputs");
+        enabled(outputs.values())
+    }
+
+    /// Run utility with arguments against backend, temporary state file and
+    /// config
+    pub fn run(&self, args: &[&str]) -> Result<()> {
+        parse(self.dir.path(), [], args).run_with(Box::new(self.backend()))
+    }
+}
+
+/// Parse arguments of utility with state file and config within `dir`
+fn parse<const N: usize>(
+    dir: &Path,
+    extra: [OsString; N],
+    args: &[&str],
+) -> Args {
+    let argv = argv(dir, extra, args);
+    Args::try_parse_from(["niri-single-output".into()].into_iter().chain(argv))
+        .expect("failed to parse arguments")
+}
+
+/// Arguments of utility with state file and config within `dir`
+fn argv<const N: usize>(
+    dir: &Path,
+    extra: [OsString; N],
+    args: &[&str],
+) -> Vec<OsString> {
+    let mut argv = vec![
+        "--state".into(),
+        dir.join("state").into_os_string(),
+        "--config".into(),
+        dir.join("config.toml").into_os_string(),
+    ];
+    argv.extend(extra);
+    argv.extend(args.iter().map(Into::into));
+    argv
+}
+
+/// Names of enabled `outputs` sorted
+fn enabled<'a>(outputs: impl IntoIterator<Item = &'a Output>) -> Vec<String> {
+    let mut enabled: Vec<String> = outputs
+        .into_iter()
+        .filter(|output| output.current_mode.is_some())
+        .map(|output| output.name.clone())
+        .collect();
+    enabled.sort();
+    enabled
+}
+
+/// Create output with single 1920x1080 mode
+pub fn output(
+    name: &str,
+    make: &str,
+    model: &str,
+    serial: Option<&str>,
+    enabled: bool,
+) -> Output {
+    let mut output = Output {
+        name: name.into(),
+        make: make.into(),
+        model: model.into(),
+        serial: serial.map(Into::into),
+        physical_size: None,
+        modes: vec![Mode {
+            width: 1920,
+            height: 1080,
+            refresh_rate: 60000,
+            is_preferred: true,
+        }],
+        current_mode: None,
+        vrr_supported: false,
+        vrr_enabled: false,
+        logical: None,
+    };
+    if enabled {
+        enable(&mut output);
+    }
+    output
+}
+
+/// The three outputs where `DP-1` is enabled
+pub fn outputs() -> Vec<Output> {
+    vec![
+        output("DP-1", "Dell", "U2720", Some("AAA"), true),
+        output("DP-2", "Dell", "P2419", None, false),
+        output("HDMI-A-1", "LG", "TV", Some("BBB"), false),
+    ]
+}
+
+fn enable(output: &mut Output) {
+    output.current_mode = Some(0);
+    output.logical = Some(LogicalOutput {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+        scale: 1.,
+        transform: Transform::Normal,
+    });
+}
+
+/// Answer single request and close connection, like niri does. The
+/// connection of [Request::EventStream] is kept to send events.
+fn serve(stream: UnixStream, mut backend: FakeBackend, shared: &Mutex<Shared>) {
+    shared.lock().unwrap().connections += 1;
+    let mut writer = stream.try_clone().expect("failed to clone stream");
+    let mut line = String::new();
+    if BufReader::new(stream).read_line(&mut line).is_err() {
+        return;
+    }
+    let request = serde_json::from_str(&line);
+    let events = matches!(request, Ok(Request::EventStream));
+    let reply = match request {
+        Ok(request) => {
+            handle(request, &mut backend, &mut shared.lock().unwrap())
+                .unwrap_or_else(|err| Err(err.to_string()))
+        }
+        Err(err) => Err(format!("error parsing request: {err}")),
+    };
+    if send(&mut writer, &reply).is_err() || !events {
+        return;
+    }
+    let Ok(events) = backend.events() else { return };
+    for event in events.flatten() {
+        if send(&mut writer, &event).is_err() {
+            break;
+        }
+    }
+}
+
+/// Write `value` as JSON line
+fn send(writer: &mut UnixStream, value: &impl Serialize) -> io::Result<()> {
+    let mut line = serde_json::to_string(value).unwrap();
+    line.push('\n');
+    writer.write_all(line.as_bytes())
+}
+
+fn handle(
+    request: Request,
+    backend: &mut FakeBackend,
+    shared: &mut Shared,
+) -> Result<Reply> {
+    let response = match request {
+        Request::Version => Response::Version(backend.version()?),
+        Request::Outputs => Response::Outputs(backend.outputs()?),
+        Request::FocusedOutput => {
+            Response::FocusedOutput(backend.focused_output()?)
+        }
+        Request::Output { output, action } => {
+            shared.calls.push(format!("{output} {action:?}"));
+            if shared.failing.contains(&output) {
+                return Ok(Err(format!("failed to apply {action:?}")));
+            }
+            if !backend.outputs()?.contains_key(&output) {
+                return Ok(Ok(Response::OutputConfigChanged(
+                    OutputConfigChanged::OutputWasMissing,
+                )));
+            }
+            let applied =
+                Response::OutputConfigChanged(OutputConfigChanged::Applied);
+            let dead = shared.dead.contains(&output);
+            if dead && matches!(action, OutputAction::On) {
+                return Ok(Ok(applied));
+            }
+            return Ok(reply(backend.output_action(&output, action)?, applied));
+        }
+        Request::Workspaces => Response::Workspaces(backend.workspaces()?),
+        Request::Windows => Response::Windows(backend.windows()?),
+        Request::EventStream => Response::Handled,
+        Request::Action(action) => return act(action, backend),
+        request => return Ok(Err(format!("unsupported request {request:?}"))),
+    };
+    Ok(Ok(response))
+}
+
+/// Perform niri action on workspaces
+fn act(action: Action, backend: &mut FakeBackend) -> Result<Reply> {
+    let (dx, dy) = match action {
+        Action::FocusWorkspace {
+            reference: WorkspaceReferenceArg::Id(id),
+        } => {
+            let result = backend.focus_workspace(id)?;
+            return Ok(reply(result, Response::Handled));
+        }
+        Action::MoveWindowToWorkspace {
+            window_id: Some(id),
+            reference: WorkspaceReferenceArg::Id(workspace),
+        } => {
+            let result = backend.move_window(id, workspace)?;
+            return Ok(reply(result, Response::Handled));
+        }
+        Action::MoveWorkspaceToMonitorLeft {} => (-1, 0),
+        Action::MoveWorkspaceToMonitorRight {} => (1, 0),
+        Action::MoveWorkspaceToMonitorUp {} => (0, -1),
+        Action::MoveWorkspaceToMonitorDown {} => (0, 1),
+        action => return Ok(Err(format!("unsupported action {action:?}"))),
+    };
+
+    // Move focused workspace to nearest output in direction
+    let outputs = backend.outputs()?;
+    let workspaces = backend.workspaces()?;
+    let Some(focused) = workspaces.iter().find(|ws| ws.is_focused) else {
+        return Ok(Ok(Response::Handled));
+    };
+    let center = |name: &str| {
+        let logical = outputs.get(name)?.logical?;
+        Some((
+            logical.x + logical.width as i32 / 2,
+            logical.y + logical.height as i32 / 2,
+        ))
+    };
+    let Some((x, y)) = focused.output.as_deref().and_then(center) else {
+        return Ok(Ok(Response::Handled));
+    };
+    let target = outputs
+        .keys()
+        .filter_map(|name| Some((name, center(name)?)))
+        .filter(|(_, (tx, ty))| (tx - x) * dx > 0 || (ty - y) * dy > 0)
+        .min_by_key(|(_, (tx, ty))| (tx - x).abs() + (ty - y).abs())
+        .map(|(name, _)| name);
+    let Some(target) = target else {
+        return Ok(Ok(Response::Handled));
+    };
+    // The moved workspace keeps focus on its new output
+    if let Err(err) = backend.move_workspace(focused.id, target)? {
+        return Ok(Err(err));
+    }
+    Ok(reply(
+        backend.focus_workspace(focused.id)?,
+        Response::Handled,
+    ))
+}
+
+/// The niri reply with `response` once action succeeds
+fn reply(result: ActionResult, response: Response) -> Reply {
+    result.map(|()| response)
+}
@@ -0,0 +1,79 @@
+mod common;
+
+use common::{outputs, FakeNiri};
+use niri_single_output::Error;
+use std::fs;
+
+#[test]
+fn missing_socket() {
+    let niri = FakeNiri::new(outputs());
+    fs::remove_file(niri.socket()).unwrap();
+    let err = niri.run(&["test"]).unwrap_err();
+    assert!(matches!(err, Error::Socket(_)));
+    assert_eq!(err.exit_code(), 3);
+}
+
+#[test]
+fn unknown_output() {
+    let niri = FakeNiri::new(outputs());
+    let err = niri.run(&["switch", "eDP-1"]).unwrap_err();
+    assert!(matches!(err, Error::UnknownOutput(_)));
+    assert_eq!(err.exit_code(), 5);
+    assert!(niri.calls().is_empty());
+}
+
+#[test]
+fn ambiguous_output() {
+    let niri = FakeNiri::new(outputs());
+    let err = niri.run(&["switch", "dell"]).unwrap_err();
+    match err {
+        Error::AmbiguousOutput(_, ref found) => {
+            assert_eq!(found, &["DP-1", "DP-2"])
+        }
+        err => panic!("unexpected error {err}"),
+    }
+    assert!(niri.calls().is_empty());
+}
+
+#[test]
+fn no_outputs() {
+    let niri = FakeNiri::new([]);
+    let err = niri.run(&["next"]).unwrap_err();
+    assert!(matches!(err, Error::NoOutputs));
+    assert_eq!(err.exit_code(), 7);
+}
+
+#[test]
+fn broken_config() {
+    let niri = FakeNiri::new(outputs());
+    niri.config("prefer = [\"DP-1\"]\nunknown = 1\n");
+    let err = niri.run(&["init"]).unwrap_err();
+    assert!(
+        matches!(err, Error::Config(_, ref msg) if msg.starts_with("line 2"))
+    );
+    assert_eq!(err.exit_code(), 8);
+}
+
+#[test]
+fn output_which_does_not_switch_on_is_reverted() {
+    let niri = FakeNiri::new(outputs());
+    niri.dead("HDMI-A-1");
+    let err = niri.run(&["switch", "HDMI-A-1"]).unwrap_err();
+
+    assert!(matches!(err, Error::NotActivated(_)));
+    assert_eq!(err.exit_code(), 9);
+    // Other outputs are never switched off
+    assert_eq!(niri.calls(), ["HDMI-A-1 On", "HDMI-A-1 Off"]);
+    assert_eq!(niri.enabled(), ["DP-1"]);
+    assert!(!niri.statefile().exists());
+}
+
+#[test]
+fn niri_error() {
+    let niri = FakeNiri::new(outputs());
+    niri.failing("DP-2");
+    let err = niri.run(&["switch", "DP-2"]).unwrap_err();
+    assert!(matches!(err, Error::Niri(_)));
+    assert_eq!(err.exit_code(), 4);
+    assert_eq!(niri.enabled(), ["DP-1"]);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{output, outputs, FakeNiri};
+use std::fs;
+
+#[test]
+fn keeps_enabled_output_without_state() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["init"]).unwrap();
+
+    assert_eq!(niri.calls(), ["DP-1 On", "DP-2 Off", "HDMI-A-1 Off"]);
+    assert_eq!(niri.enabled(), ["DP-1"]);
+    assert_eq!(niri.state().active.as_deref(), Some("DP-1"));
+}
+
+#[test]
+fn restores_remembered_output() {
+    let niri = FakeNiri::new(outputs());
+    fs::write(niri.statefile(), "HDMI-A-1\n").unwrap();
+
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.calls(), ["HDMI-A-1 On", "DP-1 Off", "DP-2 Off"]);
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
+
+#[test]
+fn follows_remembered_monitor_to_another_port() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+
+    // Reboot with TV plugged to another port
+    niri.unplug("HDMI-A-1");
+    niri.plug(output("HDMI-A-2", "LG", "TV", Some("BBB"), false));
+    niri.plug(output("DP-1", "Dell", "U2720", Some("AAA"), true));
+
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-2"]);
+    assert_eq!(niri.state().active.as_deref(), Some("HDMI-A-2"));
+}
+
+#[test]
+fn falls_back_to_history_if_remembered_is_unplugged() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    niri.run(&["switch", "DP-2"]).unwrap();
+    niri.unplug("DP-2");
+
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
+
+#[test]
+fn falls_back_to_preferred_output() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["init", "--prefer", "missing,LG TV"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
+
+#[test]
+fn falls_back_to_preferred_output_from_config() {
+    let niri = FakeNiri::new(outputs());
+    niri.config("prefer = [\"tv\"]\n[aliases]\ntv = \"HDMI-A-1\"\n");
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
+
+#[test]
+fn enables_first_output_if_none_enabled() {
+    let mut outputs = outputs();
+    for output in &mut outputs {
+        output.current_mode = None;
+        output.logical = None;
+    }
+    let niri = FakeNiri::new(outputs);
+    niri.run(&["init", "--order", "list", "--list", "HDMI-A-1,DP-1"])
+        .unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
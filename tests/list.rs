@@ -0,0 +1,30 @@
+mod common;
+
+use common::{outputs, FakeNiri};
+
+#[test]
+fn list_follows_cycle_order() {
+    let niri = FakeNiri::new(outputs());
+    niri.config("[order]\nby = \"list\"\nlist = [\"HDMI-A-1\", \"DP-1\"]\n");
+    let (code, stdout) = niri.exec(&["list"]);
+    assert_eq!(code, 0);
+    let mut listed: Vec<&str> = stdout.lines().collect();
+    assert_eq!(listed, ["HDMI-A-1", "DP-1", "DP-2"]);
+
+    let mut visited = Vec::new();
+    for _ in 0..listed.len() {
+        niri.run(&["next"]).unwrap();
+        visited.extend(niri.enabled());
+    }
+    // The cycle starts after focused `DP-1`
+    listed.rotate_left(2);
+    assert_eq!(visited, listed);
+}
+
+#[test]
+fn list_renders_format() {
+    let niri = FakeNiri::new(outputs());
+    let (code, stdout) = niri.exec(&["list", "--format", r"{index}\t{name}"]);
+    assert_eq!(code, 0);
+    assert_eq!(stdout, "0\tDP-1\n1\tDP-2\n2\tHDMI-A-1\n");
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{outputs, FakeNiri};
+
+const CONFIG: &str = r#"
+[profiles.desk]
+outputs = ["DP-1", "DP-2"]
+
+[profiles.desk.settings.DP-2]
+position = { x = 1920, y = 0 }
+
+[profiles.tv]
+outputs = ["HDMI-A-1"]
+
+[profiles.away]
+outputs = ["DP-1", "eDP-1"]
+"#;
+
+#[test]
+fn profile_switches_on_its_outputs() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(CONFIG);
+    niri.workspace("HDMI-A-1", Some("movies"), true);
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    niri.calls();
+
+    niri.run(&["profile", "apply", "desk"]).unwrap();
+    assert_eq!(
+        niri.calls(),
+        [
+            "DP-1 On",
+            "DP-2 On",
+            "DP-2 Position { position: Specific(ConfiguredPosition { x: 1920, y: 0 }) }",
+            "HDMI-A-1 Off",
+        ]
+    );
+    assert_eq!(niri.enabled(), ["DP-1", "DP-2"]);
+    // The workspaces are moved to the main output of profile
+    assert_eq!(niri.workspaces("DP-1"), ["movies"]);
+
+    let state = niri.state();
+    assert_eq!(state.active.as_deref(), Some("DP-1"));
+    assert_eq!(state.profile.as_deref(), Some("desk"));
+}
+
+#[test]
+fn next_profile_skips_unconnected_ones() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(CONFIG);
+    let mut visited = Vec::new();
+    for _ in 0..3 {
+        niri.run(&["profile", "next"]).unwrap();
+        visited.push(niri.enabled().join(","));
+    }
+    assert_eq!(visited, ["DP-1,DP-2", "HDMI-A-1", "DP-1,DP-2"]);
+}
+
+#[test]
+fn switch_leaves_profile_and_init_restores_it() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(CONFIG);
+    niri.run(&["profile", "apply", "desk"]).unwrap();
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["DP-1", "DP-2"]);
+
+    niri.run(&["switch", "DP-2"]).unwrap();
+    assert_eq!(niri.state().profile, None);
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["DP-2"]);
+}
+
+#[test]
+fn unknown_profile_fails() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(CONFIG);
+    let err = niri.run(&["profile", "apply", "home"]).unwrap_err();
+    assert_eq!(err.exit_code(), 11);
+    let err = niri.run(&["profile", "apply", "away"]).unwrap_err();
+    assert_eq!(err.exit_code(), 5);
+    assert_eq!(niri.enabled(), ["DP-1"]);
+}
+
+#[test]
+fn unconfirmed_switch_returns_to_profile() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(CONFIG);
+    niri.run(&["profile", "apply", "desk"]).unwrap();
+    let err = niri
+        .run(&["switch", "HDMI-A-1", "--confirm", "1"])
+        .unwrap_err();
+
+    assert_eq!(err.exit_code(), 10);
+    assert_eq!(niri.enabled(), ["DP-1", "DP-2"]);
+    let state = niri.state();
+    assert_eq!(state.profile.as_deref(), Some("desk"));
+    assert!(state.pending.is_none());
+}
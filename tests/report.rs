@@ -0,0 +1,51 @@
+mod common;
+
+use common::{outputs, FakeNiri};
+use serde_json::{json, Value};
+
+/// The actions of report as `<output> <action>`
+fn actions(report: &Value) -> Vec<String> {
+    report["actions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|action| {
+            let output = action["output"].as_str().unwrap();
+            format!("{output} {}", action["action"].as_str().unwrap())
+        })
+        .collect()
+}
+
+#[test]
+fn switch_is_reported() {
+    let niri = FakeNiri::new(outputs());
+    let report = niri.report(&["switch", "HDMI-A-1"]);
+
+    assert_eq!(report["previous"], "DP-1");
+    assert_eq!(report["active"], "HDMI-A-1");
+    assert_eq!(actions(&report), ["HDMI-A-1 On", "DP-1 Off", "DP-2 Off"]);
+    assert!(report.get("error").is_none());
+}
+
+#[test]
+fn failure_is_reported() {
+    let niri = FakeNiri::new(outputs());
+    niri.dead("HDMI-A-1");
+    let report = niri.report(&["switch", "HDMI-A-1"]);
+
+    assert_eq!(report["error"]["code"], 9);
+    assert!(report.get("active").is_none());
+    // The output which did not come up is switched back off
+    assert_eq!(actions(&report), ["HDMI-A-1 On", "HDMI-A-1 Off"]);
+    assert_eq!(niri.enabled(), ["DP-1"]);
+
+    let report = niri.report(&["switch", "VGA-1"]);
+    assert_eq!(
+        report["error"],
+        json!({
+            "code": 5,
+            "message": "output VGA-1 is not connected",
+        })
+    );
+    assert!(report.get("actions").is_none());
+}
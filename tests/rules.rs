@@ -0,0 +1,98 @@
+mod common;
+
+use common::{outputs, FakeNiri};
+
+const CONFIG: &str = r#"
+[profiles.desk]
+outputs = ["DP-1", "DP-2"]
+
+[[rules]]
+connected = ["DP-1", "DP-2", "HDMI-A-1"]
+output = "HDMI-A-1"
+
+[[rules]]
+connected = ["DP-1", "DP-2"]
+profile = "desk"
+"#;
+
+#[test]
+fn init_chooses_outputs_by_rules() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(CONFIG);
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+
+    niri.unplug("HDMI-A-1");
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["DP-1", "DP-2"]);
+}
+
+#[test]
+fn init_falls_back_to_remembered_output() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(CONFIG);
+    niri.unplug("DP-1");
+    niri.run(&["switch", "DP-2"]).unwrap();
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["DP-2"]);
+}
+
+#[test]
+fn rules_match_excluded_outputs() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(
+        r#"
+        exclude = ["HDMI-A-1"]
+
+        [profiles.desk]
+        outputs = ["DP-1", "DP-2"]
+
+        [[rules]]
+        connected = ["HDMI-A-1", "DP-1", "DP-2"]
+        profile = "desk"
+
+        [[rules]]
+        connected = ["HDMI-A-1", "DP-1"]
+        output = "DP-1"
+        "#,
+    );
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["DP-1", "DP-2"]);
+
+    niri.unplug("DP-2");
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.enabled(), ["DP-1"]);
+    // The excluded output is never switched
+    assert!(niri.calls().iter().all(|call| !call.starts_with("HDMI")));
+}
+
+#[test]
+fn rule_with_unknown_profile_is_rejected() {
+    let niri = FakeNiri::new(outputs());
+    niri.config("[[rules]]\nconnected = [\"DP-1\"]\nprofile = \"tv\"\n");
+    let err = niri.run(&["init"]).unwrap_err();
+    assert_eq!(err.exit_code(), 8);
+    assert!(err
+        .to_string()
+        .ends_with("rule #1 refers to unknown profile tv"));
+}
+
+#[test]
+fn rule_with_unlisted_output_is_rejected() {
+    let niri = FakeNiri::new(outputs());
+    niri.config(
+        r#"
+        [profiles.desk]
+        outputs = ["DP-1", "DP-2"]
+
+        [[rules]]
+        connected = ["DP-1", "HDMI-A-1"]
+        profile = "desk"
+        "#,
+    );
+    let err = niri.run(&["init"]).unwrap_err();
+    assert_eq!(err.exit_code(), 8);
+    assert!(err
+        .to_string()
+        .ends_with("rule #1 switches on DP-2 not listed within connected"));
+}
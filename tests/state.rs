@@ -0,0 +1,71 @@
+mod common;
+
+use common::{outputs, FakeNiri};
+use niri_single_output::{Error, STATE_VERSION};
+use std::fs;
+
+#[test]
+fn switch_records_history() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    niri.run(&["switch", "DP-2"]).unwrap();
+
+    let state = niri.state();
+    assert_eq!(state.version, STATE_VERSION);
+    assert_eq!(state.active.as_deref(), Some("DP-2"));
+    let history: Vec<&str> =
+        state.history.iter().map(|r| r.name.as_str()).collect();
+    assert_eq!(history[0], "DP-2");
+    assert_eq!(history.len(), 3);
+
+    // The mode of previously active output is remembered
+    let tv = state.history.iter().find(|r| r.name == "HDMI-A-1").unwrap();
+    assert_eq!(tv.identity.as_deref(), Some("LG TV BBB"));
+    assert!(tv.mode.is_some() && tv.logical.is_some());
+}
+
+#[test]
+fn plain_state_file_is_migrated() {
+    let niri = FakeNiri::new(outputs());
+    fs::write(niri.statefile(), "HDMI-A-1").unwrap();
+
+    let state = niri.state();
+    assert_eq!(state.active.as_deref(), Some("HDMI-A-1"));
+    assert_eq!(state.history.len(), 1);
+
+    niri.run(&["next"]).unwrap();
+    let content = fs::read_to_string(niri.statefile()).unwrap();
+    assert!(content.starts_with('{'));
+}
+
+#[test]
+fn newer_state_file_is_rejected() {
+    let niri = FakeNiri::new(outputs());
+    let content = format!(
+        "{{\"version\": {}, \"active\": \"DP-1\"}}",
+        STATE_VERSION + 1
+    );
+    fs::write(niri.statefile(), &content).unwrap();
+
+    let err = niri.run(&["init"]).unwrap_err();
+    assert!(matches!(err, Error::State(_, _)));
+    assert_eq!(err.exit_code(), 6);
+    // The file of newer version is left untouched
+    assert_eq!(fs::read_to_string(niri.statefile()).unwrap(), content);
+}
+
+#[test]
+fn unconfirmed_switch_is_reverted() {
+    let niri = FakeNiri::new(outputs());
+    let err = niri
+        .run(&["switch", "HDMI-A-1", "--confirm", "1"])
+        .unwrap_err();
+
+    assert!(
+        matches!(err, Error::NotConfirmed(ref output) if output == "HDMI-A-1")
+    );
+    assert_eq!(niri.enabled(), ["DP-1"]);
+    let state = niri.state();
+    assert_eq!(state.active.as_deref(), Some("DP-1"));
+    assert!(state.pending.is_none());
+}
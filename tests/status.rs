@@ -0,0 +1,63 @@
+mod common;
+
+use common::{outputs, FakeNiri};
+use std::{thread, time::Duration};
+
+#[test]
+fn status_shows_history_and_pending_switch() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    niri.run(&["switch", "DP-2"]).unwrap();
+
+    thread::scope(|scope| {
+        let switch =
+            scope.spawn(|| niri.run(&["switch", "HDMI-A-1", "--confirm", "5"]));
+        while niri.state().pending.is_none() {
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let report = niri.report(&["status"]);
+        let status = &report["status"];
+        assert_eq!(status["active"], "HDMI-A-1");
+        assert_eq!(status["pending"]["previous"], "DP-2");
+        assert_eq!(status["pending"]["output"], "HDMI-A-1");
+        let outputs: Vec<_> = status["outputs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|output| {
+                let name = output["name"].as_str().unwrap();
+                (name, output["active"].clone(), output["remembered"].clone())
+            })
+            .collect();
+        assert_eq!(
+            outputs,
+            [
+                ("DP-1", false.into(), 2.into()),
+                ("DP-2", false.into(), 1.into()),
+                ("HDMI-A-1", true.into(), 0.into()),
+            ]
+        );
+
+        let (code, stdout) = niri.exec(&["status"]);
+        assert_eq!(code, 0);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(
+            lines[..4],
+            [
+                "NAME      OUTPUT      MODE              ENABLED  REMEMBERED",
+                "DP-1      Dell U2720  -                 no       #2",
+                "DP-2      Dell P2419  -                 no       #1",
+                "HDMI-A-1  LG TV       1920x1080@60.000  yes      active",
+            ]
+        );
+        assert!(lines.contains(&"Remembered output: HDMI-A-1"));
+        assert!(lines.contains(
+            &"Waiting for confirmation of switch from DP-2 to HDMI-A-1"
+        ));
+
+        niri.run(&["confirm"]).unwrap();
+        switch.join().unwrap().unwrap();
+    });
+    assert!(niri.state().pending.is_none());
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use common::{output, outputs, FakeNiri};
+
+#[test]
+fn next_switches_on_first_then_off_others() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["next"]).unwrap();
+
+    assert_eq!(niri.calls(), ["DP-2 On", "DP-1 Off", "HDMI-A-1 Off"]);
+    assert_eq!(niri.enabled(), ["DP-2"]);
+}
+
+#[test]
+fn next_reconnects_after_each_reply() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["next"]).unwrap();
+
+    assert_eq!(niri.enabled(), ["DP-2"]);
+    // Niri closes connection after reply, so each request reconnects
+    assert!(niri.connections() >= niri.calls().len() + 2);
+}
+
+#[test]
+fn next_wraps_around() {
+    let niri = FakeNiri::new(outputs());
+    let mut visited = Vec::new();
+    for _ in 0..3 {
+        niri.run(&["next"]).unwrap();
+        visited.extend(niri.enabled());
+    }
+    assert_eq!(visited, ["DP-2", "HDMI-A-1", "DP-1"]);
+}
+
+#[test]
+fn prev_walks_backward() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["prev"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
+
+#[test]
+fn next_follows_explicit_order() {
+    let niri = FakeNiri::new(outputs());
+    niri.config("[order]\nby = \"list\"\nlist = [\"DP-1\", \"HDMI-A-1\"]\n");
+    niri.run(&["next"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
+
+#[test]
+fn excluded_outputs_are_untouched() {
+    let niri = FakeNiri::new(outputs());
+    niri.config("exclude = [\"DP-2\"]\n");
+    niri.run(&["next"]).unwrap();
+    assert_eq!(niri.calls(), ["HDMI-A-1 On", "DP-1 Off"]);
+}
+
+#[test]
+fn switch_finds_output_by_identity() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["switch", "lg tv"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
+
+#[test]
+fn settings_are_applied_to_switched_output() {
+    let niri = FakeNiri::new(outputs());
+    niri.config("[outputs.HDMI-A-1]\nscale = 2.0\n");
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    assert_eq!(
+        niri.calls(),
+        [
+            "HDMI-A-1 On",
+            "HDMI-A-1 Scale { scale: Specific(2.0) }",
+            "DP-1 Off",
+            "DP-2 Off"
+        ]
+    );
+}
+
+#[test]
+fn settings_by_name_win_over_identity_and_alias() {
+    let niri = FakeNiri::new(outputs());
+    let scale = |config: &str| {
+        niri.config(config);
+        niri.run(&["switch", "HDMI-A-1"]).unwrap();
+        niri.run(&["switch", "DP-1"]).unwrap();
+        let calls = niri.calls();
+        let scale = calls.iter().find(|call| call.contains("Scale"));
+        scale.map(|call| call.replace("HDMI-A-1 Scale", ""))
+    };
+    let aliases = r#"
+        [aliases]
+        tv = "HDMI-A-1"
+        lg = "LG TV BBB"
+
+        [outputs.tv]
+        scale = 4.0
+
+        [outputs.lg]
+        scale = 5.0
+    "#;
+    let identity = format!("{aliases}\n[outputs.\"LG TV BBB\"]\nscale = 3.0");
+    let name = format!("{identity}\n[outputs.HDMI-A-1]\nscale = 2.0");
+
+    assert_eq!(scale(&name).unwrap(), " { scale: Specific(2.0) }");
+    assert_eq!(scale(&identity).unwrap(), " { scale: Specific(3.0) }");
+    // The aliases are compared by name
+    assert_eq!(scale(aliases).unwrap(), " { scale: Specific(5.0) }");
+}
+
+#[test]
+fn dry_run_does_not_touch_niri() {
+    let niri = FakeNiri::new(outputs());
+    niri.run(&["next", "--dry-run"]).unwrap();
+    assert!(niri.calls().is_empty());
+    assert_eq!(niri.enabled(), ["DP-1"]);
+    assert!(!niri.statefile().exists());
+}
+
+#[test]
+fn next_starts_from_focused_output() {
+    let mut outputs = outputs();
+    outputs[1] = output("DP-2", "Dell", "P2419", None, true);
+    let niri = FakeNiri::new(outputs);
+    niri.focus("DP-2");
+
+    niri.run(&["next"]).unwrap();
+    assert_eq!(niri.enabled(), ["HDMI-A-1"]);
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{outputs, FakeNiri};
+use std::fs;
+
+#[test]
+fn windows_are_returned_on_return() {
+    let niri = FakeNiri::new(outputs());
+    let mail = niri.workspace("DP-1", Some("mail"), false);
+    let code = niri.workspace("DP-1", Some("code"), false);
+    let editor = niri.window(code, "editor", "main.rs");
+    let browser = niri.window(mail, "browser", "docs");
+
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    niri.move_window(editor, mail);
+    niri.run(&["switch", "DP-1"]).unwrap();
+    assert_eq!(niri.window_workspace(editor), Some(code));
+    assert_eq!(niri.window_workspace(browser), Some(mail));
+
+    // The returned windows are forgotten, so they may be moved freely
+    assert!(niri.state().history[0].windows.is_empty());
+    niri.move_window(editor, mail);
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.window_workspace(editor), Some(mail));
+}
+
+#[test]
+fn windows_are_found_by_app_id_after_restart() {
+    let niri = FakeNiri::new(outputs());
+    let mail = niri.workspace("DP-1", Some("mail"), false);
+    let code = niri.workspace("DP-1", Some("code"), false);
+    let editor = niri.window(mail, "editor", "main.rs");
+    fs::write(
+        niri.statefile(),
+        r#"{
+            "version": 1,
+            "active": "HDMI-A-1",
+            "history": [{
+                "name": "DP-1",
+                "last_used": 0,
+                "mode": null,
+                "logical": null,
+                "windows": [{
+                    "id": 42,
+                    "app_id": "editor",
+                    "title": "main.rs",
+                    "workspace": {"id": 7, "name": "code", "idx": 2}
+                }]
+            }]
+        }"#,
+    )
+    .unwrap();
+
+    niri.run(&["switch", "DP-1"]).unwrap();
+    assert_eq!(niri.window_workspace(editor), Some(code));
+}
+
+#[test]
+fn dry_run_does_not_move_windows() {
+    let niri = FakeNiri::new(outputs());
+    let mail = niri.workspace("DP-1", Some("mail"), false);
+    let code = niri.workspace("DP-1", Some("code"), false);
+    let editor = niri.window(code, "editor", "main.rs");
+
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    niri.move_window(editor, mail);
+    niri.run(&["--dry-run", "switch", "DP-1"]).unwrap();
+    assert_eq!(niri.window_workspace(editor), Some(mail));
+}
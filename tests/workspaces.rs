@@ -0,0 +1,141 @@
+mod common;
+
+use common::{output, outputs, FakeNiri};
+use std::fs;
+
+/// Two enabled outputs side by side with workspaces, the `HDMI-A-1` is off
+fn niri() -> FakeNiri {
+    let mut outputs = outputs();
+    let mut right = output("DP-2", "Dell", "P2419", None, true);
+    right.logical.as_mut().unwrap().x = 1920;
+    outputs[1] = right;
+
+    let niri = FakeNiri::new(outputs);
+    niri.workspace("DP-1", Some("mail"), true);
+    niri.workspace("DP-1", None, false);
+    niri.workspace("DP-1", Some("code"), true);
+    niri.workspace("DP-2", Some("web"), false);
+    niri.workspace("DP-2", None, true);
+    niri
+}
+
+#[test]
+fn workspaces_are_moved_in_output_order() {
+    let niri = niri();
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    // The empty workspace is left to niri
+    assert_eq!(
+        niri.workspaces("HDMI-A-1"),
+        ["mail", "code", "web", "5", "2"]
+    );
+}
+
+#[test]
+fn workspaces_are_moved_in_index_order() {
+    let niri = niri();
+    niri.config("[workspaces]\norder = \"index\"\n");
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    assert_eq!(
+        niri.workspaces("HDMI-A-1"),
+        ["mail", "web", "5", "code", "2"]
+    );
+}
+
+#[test]
+fn workspaces_are_moved_in_name_order() {
+    let niri = niri();
+    niri.config("[workspaces]\norder = \"name\"\n");
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    assert_eq!(
+        niri.workspaces("HDMI-A-1"),
+        ["code", "mail", "web", "5", "2"]
+    );
+}
+
+#[test]
+fn migration_may_be_disabled() {
+    let niri = niri();
+    niri.config("[workspaces]\nmigrate = false\n");
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    // Niri moves workspaces of DP-1 to DP-2 first
+    assert_eq!(
+        niri.workspaces("HDMI-A-1"),
+        ["web", "5", "mail", "2", "code"]
+    );
+}
+
+#[test]
+fn focused_workspace_stays_focused() {
+    let niri = niri();
+    let code = 3;
+    niri.focus_workspace(code);
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    assert_eq!(niri.focused_workspace(), Some(code));
+}
+
+#[test]
+fn dry_run_does_not_move_workspaces() {
+    let niri = niri();
+    niri.run(&["--dry-run", "--json", "switch", "HDMI-A-1"])
+        .unwrap();
+    assert_eq!(niri.workspaces("DP-1"), ["mail", "2", "code"]);
+    assert!(niri.workspaces("HDMI-A-1").is_empty());
+}
+
+#[test]
+fn focused_workspace_is_restored_on_return() {
+    let niri = FakeNiri::new(outputs());
+    let mail = niri.workspace("DP-1", Some("mail"), true);
+    let code = niri.workspace("DP-1", Some("code"), true);
+    niri.focus_workspace(code);
+
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    niri.focus_workspace(mail);
+    niri.run(&["switch", "DP-1"]).unwrap();
+    assert_eq!(niri.focused_workspace(), Some(code));
+
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    assert_eq!(niri.focused_workspace(), Some(mail));
+}
+
+#[test]
+fn restored_workspace_is_forgotten() {
+    let niri = FakeNiri::new(outputs());
+    let mail = niri.workspace("DP-1", Some("mail"), true);
+    let code = niri.workspace("DP-1", Some("code"), true);
+    niri.focus_workspace(code);
+
+    niri.run(&["switch", "HDMI-A-1"]).unwrap();
+    niri.run(&["switch", "DP-1"]).unwrap();
+    assert_eq!(niri.focused_workspace(), Some(code));
+    assert_eq!(niri.state().history[0].workspace, None);
+
+    niri.focus_workspace(mail);
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.focused_workspace(), Some(mail));
+}
+
+#[test]
+fn focused_workspace_is_found_by_name_after_restart() {
+    let niri = FakeNiri::new(outputs());
+    niri.workspace("DP-1", Some("mail"), true);
+    let code = niri.workspace("DP-1", Some("code"), true);
+    fs::write(
+        niri.statefile(),
+        r#"{
+            "version": 1,
+            "active": "DP-1",
+            "history": [{
+                "name": "DP-1",
+                "last_used": 0,
+                "mode": null,
+                "logical": null,
+                "workspace": {"id": 42, "name": "code", "idx": 3}
+            }]
+        }"#,
+    )
+    .unwrap();
+
+    niri.run(&["init"]).unwrap();
+    assert_eq!(niri.focused_workspace(), Some(code));
+}